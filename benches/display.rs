@@ -0,0 +1,28 @@
+use std::fmt::Write;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+fn single_code(c: &mut Criterion) {
+	let mut s = String::with_capacity(64);
+	c.bench_function("code", |b| b.iter(|| {
+		s.clear();
+		write!(s, "{}", black_box(ansi_gfx::BRIGHT_RED_BG)).unwrap();
+	}));
+	// Same output through the general code list path
+	let style = ansi_gfx::mode!(BRIGHT_RED_BG);
+	c.bench_function("code_list", |b| b.iter(|| {
+		s.clear();
+		write!(s, "{}", black_box(style)).unwrap();
+	}));
+}
+
+fn combined(c: &mut Criterion) {
+	let mut s = String::with_capacity(64);
+	let style = ansi_gfx::mode!(BOLD; FG RGB 243, 159, 24; BLUE_BG);
+	c.bench_function("mode", |b| b.iter(|| {
+		s.clear();
+		write!(s, "{}", black_box(style)).unwrap();
+	}));
+}
+
+criterion_group!(benches, single_code, combined);
+criterion_main!(benches);
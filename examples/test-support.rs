@@ -44,7 +44,7 @@ fn main() {
 		if i == 8 {
 			println!();
 		}
-		let fg = if i == 0 { ansi_gfx::WHITE } else { ansi_gfx::BLACK };
+		let fg = ansi_gfx::palette::contrasting_fg(ansi_gfx::palette::palette_to_rgb(i));
 		let mode = ansi_gfx::mode!({fg}; BG PAL i);
 		print!("{}{:>4} {}", mode.erase(), i, ansi_gfx::RESET);
 	}
@@ -57,7 +57,7 @@ fn main() {
 			println!();
 		}
 		n += 1;
-		let fg = if i < 34 { ansi_gfx::WHITE } else { ansi_gfx::BLACK };
+		let fg = ansi_gfx::palette::contrasting_fg(ansi_gfx::palette::palette_to_rgb(i));
 		print!("{}{:>4} {}", ansi_gfx::mode!({fg}; BG PAL i), i, ansi_gfx::RESET);
 	}
 
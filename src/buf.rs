@@ -0,0 +1,585 @@
+use core::fmt::{self, Write};
+use alloc::string::String;
+use alloc::vec::Vec;
+use super::{events, segments, Code, Color, Event, Print};
+use super::palette::palette_to_rgb;
+use super::state::{Segment, State};
+
+/// Owned style built at runtime.
+///
+/// ```
+/// let mut style = ansi_gfx::PrintBuf::new();
+/// style.push(ansi_gfx::BOLD);
+/// style.push(ansi_gfx::RED);
+/// assert_eq!(format!("{}", style), "\x1b[1;31m");
+/// ```
+pub type PrintBuf = Print<Vec<u8>>;
+
+impl Print<Vec<u8>> {
+	/// Creates an empty style.
+	#[inline]
+	pub const fn new() -> PrintBuf {
+		Print { __codes: Vec::new() }
+	}
+
+	/// Creates an empty style with room for at least `capacity` codes.
+	///
+	/// Extended colors take 3 or 5 codes.
+	#[inline]
+	pub fn with_capacity(capacity: usize) -> PrintBuf {
+		Print { __codes: Vec::with_capacity(capacity) }
+	}
+
+	/// Returns the number of codes the style can hold without reallocating.
+	#[inline]
+	pub fn capacity(&self) -> usize {
+		self.__codes.capacity()
+	}
+
+	/// Appends a code.
+	#[inline]
+	pub fn push(&mut self, code: Code) {
+		self.__codes.push(code.__byte);
+	}
+
+	/// Toggles an attribute.
+	///
+	/// If the attribute is currently set, every occurrence is replaced with its reset (see [`Code::reset_for`]).
+	/// If another attribute sharing that reset is set (eg. `DIM` for `BOLD`) the occurrences are removed instead.
+	/// Otherwise a previous reset is replaced with the attribute, or the attribute is appended.
+	/// Toggling the same attribute twice returns to the original style.
+	///
+	/// Does nothing if the code is not an attribute.
+	///
+	/// ```
+	/// let mut style = ansi_gfx::PrintBuf::new();
+	/// style.push(ansi_gfx::BOLD);
+	/// style.push(ansi_gfx::RED);
+	/// style.toggle(ansi_gfx::BOLD);
+	/// assert_eq!(format!("{}", style), "\x1b[22;31m");
+	/// style.toggle(ansi_gfx::BOLD);
+	/// assert_eq!(format!("{}", style), "\x1b[1;31m");
+	/// ```
+	pub fn toggle(&mut self, attr: Code) {
+		let reset = match attr.reset_for() {
+			Some(reset) if attr.is_attribute() => reset,
+			_ => return,
+		};
+		// Other attributes turned off by the same reset, eg. DIM for BOLD
+		let siblings = (1..=9u8)
+			.filter(|&n| n != attr.__byte && Code { __byte: n }.reset_for() == Some(reset))
+			.fold(0u16, |mask, n| mask | 1 << n);
+		let before = State::new(&self.__codes);
+		if before.on & (1 << attr.__byte) != 0 {
+			// Resetting would also turn off the other attributes, drop the attribute instead
+			if before.on & siblings != 0 {
+				self.__codes = segments(&self.__codes).filter(|&segment| segment != [attr.__byte]).flatten().copied().collect();
+			}
+			else {
+				self.replace_segments(attr, reset);
+			}
+			return;
+		}
+		let mut after = State::default();
+		for segment in segments(&self.__codes) {
+			after.apply(if segment == [reset.__byte] { core::slice::from_ref(&attr.__byte) } else { segment });
+		}
+		// Replacing a shared reset must not turn its other attributes back on
+		if after.on & (1 << attr.__byte) != 0 && after.on & siblings == before.on & siblings {
+			self.replace_segments(reset, attr);
+		}
+		else {
+			self.push(attr);
+		}
+	}
+
+	fn replace_segments(&mut self, from: Code, to: Code) {
+		let mut i = 0;
+		while let Some(segment) = segments(&self.__codes[i..]).next() {
+			let len = segment.len();
+			if segment == [from.__byte] {
+				self.__codes[i] = to.__byte;
+			}
+			i += len;
+		}
+	}
+
+	/// Layers a style over a base style.
+	///
+	/// The colors and attributes set by the override win, everything else is inherited from the base.
+	/// The result is the net effect of both styles with redundant codes removed, eg. for layering a theme and its overrides.
+	///
+	/// ```
+	/// use ansi_gfx::{mode, PrintBuf};
+	/// let style = PrintBuf::compose(&mode!(BOLD; RED), &mode!(BLUE; RED_BG));
+	/// assert_eq!(format!("{}", style), "\x1b[1;34;41m");
+	/// ```
+	pub fn compose(base: &Print<impl AsRef<[u8]>>, over: &Print<impl AsRef<[u8]>>) -> PrintBuf {
+		let mut state = State::new(base.__codes.as_ref());
+		for segment in segments(over.__codes.as_ref()) {
+			state.apply(segment);
+		}
+		let mut print = PrintBuf::new();
+		state.for_each_segment(|segment| print.__codes.extend_from_slice(segment));
+		print
+	}
+}
+
+impl Default for Print<Vec<u8>> {
+	#[inline]
+	fn default() -> PrintBuf {
+		PrintBuf::new()
+	}
+}
+
+impl<T: AsRef<[u8]>> Print<T> {
+	/// Copies the codes into an owned style.
+	#[inline]
+	pub fn to_owned_bytes(&self) -> PrintBuf {
+		Print { __codes: self.__codes.as_ref().to_vec() }
+	}
+
+	/// Splits the style into its foreground and background parts.
+	///
+	/// The background part contains the background colors, the foreground part contains everything else including the attributes.
+	/// Extended colors are kept intact.
+	///
+	/// ```
+	/// let (fg, bg) = ansi_gfx::mode!(BOLD; RED; BLUE_BG).split_fg_bg();
+	/// assert_eq!(format!("{}", fg), "\x1b[1;31m");
+	/// assert_eq!(format!("{}", bg), "\x1b[44m");
+	/// ```
+	pub fn split_fg_bg(&self) -> (PrintBuf, PrintBuf) {
+		let mut fg = PrintBuf::new();
+		let mut bg = PrintBuf::new();
+		for segment in segments(self.__codes.as_ref()) {
+			let part = if (Code { __byte: segment[0] }).is_background() { &mut bg } else { &mut fg };
+			part.__codes.extend_from_slice(segment);
+		}
+		(fg, bg)
+	}
+
+	/// Returns only the foreground colors of the style.
+	///
+	/// Unlike [`split_fg_bg`](Self::split_fg_bg) the attributes and resets are dropped.
+	///
+	/// ```
+	/// let fg = ansi_gfx::mode!(BOLD; FG PAL 28; BLUE_BG).fg_only();
+	/// assert_eq!(format!("{}", fg), "\x1b[38;5;28m");
+	/// ```
+	pub fn fg_only(&self) -> PrintBuf {
+		self.filter_segments(Code::is_foreground)
+	}
+
+	/// Returns only the background colors of the style.
+	///
+	/// Unlike [`split_fg_bg`](Self::split_fg_bg) the attributes and resets are dropped.
+	///
+	/// ```
+	/// let bg = ansi_gfx::mode!(BOLD; RED; BLUE_BG).bg_only();
+	/// assert_eq!(format!("{}", bg), "\x1b[44m");
+	/// ```
+	pub fn bg_only(&self) -> PrintBuf {
+		self.filter_segments(Code::is_background)
+	}
+
+	/// Keeps the codes (including the extended color arguments) whose leading code matches.
+	fn filter_segments(&self, f: fn(Code) -> bool) -> PrintBuf {
+		let mut print = PrintBuf::new();
+		for segment in segments(self.__codes.as_ref()) {
+			if f(Code { __byte: segment[0] }) {
+				print.__codes.extend_from_slice(segment);
+			}
+		}
+		print
+	}
+
+	/// Transforms every color of the style.
+	///
+	/// Named, palette and RGB colors of both the foreground and background are passed to the closure, the attributes are left intact.
+	/// The returned color keeps the ground of the original color.
+	///
+	/// ```
+	/// use ansi_gfx::{Color, RED, BLUE};
+	/// let style = ansi_gfx::mode!(BOLD; RED; BG PAL 28);
+	/// let swapped = style.map_colors(|color| match color {
+	/// 	Color::Named(RED) => Color::Named(BLUE),
+	/// 	color => color,
+	/// });
+	/// assert_eq!(format!("{}", swapped), "\x1b[1;34;48;5;28m");
+	/// ```
+	pub fn map_colors(&self, mut f: impl FnMut(Color) -> Color) -> PrintBuf {
+		let mut print = PrintBuf::new();
+		for segment in segments(self.__codes.as_ref()) {
+			let code = Code { __byte: segment[0] };
+			let color = match *segment {
+				[38 | 48, 5, index] => Color::Palette(index),
+				[38 | 48, 2, red, green, blue] => Color::Rgb(red, green, blue),
+				[38 | 48, ..] => {
+					// Truncated extended color
+					print.__codes.extend_from_slice(segment);
+					continue;
+				},
+				_ if code.is_foreground() || code.is_background() => Color::Named(code),
+				_ => {
+					print.__codes.extend_from_slice(segment);
+					continue;
+				},
+			};
+			print.__codes.extend_from_slice(f(color).codes(code.is_background()).as_ref());
+		}
+		print
+	}
+}
+
+/// Static or computed style.
+///
+/// Lets functions return either a static preset or a style computed at runtime.
+///
+/// ```
+/// use ansi_gfx::{CowPrint, PrintBuf};
+/// fn style(custom: Option<PrintBuf>) -> CowPrint {
+/// 	match custom {
+/// 		Some(style) => CowPrint::from(style),
+/// 		None => CowPrint::from(ansi_gfx::mode!(BOLD; RED).erase()),
+/// 	}
+/// }
+/// assert_eq!(format!("{}", style(None)), "\x1b[1;31m");
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[must_use]
+pub enum CowPrint {
+	/// Codes of a static style.
+	Borrowed(&'static [u8]),
+	/// Codes of an owned style.
+	Owned(Vec<u8>),
+}
+
+impl CowPrint {
+	/// Returns the codes.
+	#[inline]
+	pub fn bytes(&self) -> &[u8] {
+		match self {
+			CowPrint::Borrowed(codes) => codes,
+			CowPrint::Owned(codes) => codes,
+		}
+	}
+}
+
+impl fmt::Display for CowPrint {
+	#[inline]
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		super::write_codes(self.bytes(), f)
+	}
+}
+
+impl From<Print<&'static [u8]>> for CowPrint {
+	#[inline]
+	fn from(print: Print<&'static [u8]>) -> CowPrint {
+		CowPrint::Borrowed(print.__codes)
+	}
+}
+
+impl From<PrintBuf> for CowPrint {
+	#[inline]
+	fn from(print: PrintBuf) -> CowPrint {
+		CowPrint::Owned(print.__codes)
+	}
+}
+
+impl From<CowPrint> for PrintBuf {
+	#[inline]
+	fn from(print: CowPrint) -> PrintBuf {
+		match print {
+			CowPrint::Borrowed(codes) => Print { __codes: codes.to_vec() },
+			CowPrint::Owned(codes) => Print { __codes: codes },
+		}
+	}
+}
+
+/// Encodes an RGB bitmap as text using half blocks.
+///
+/// Every character cell shows two pixels stacked vertically: the upper half block `▀` in the foreground color of the top pixel over the background color of the bottom pixel.
+/// The pixels are in row-major order, every line ends with a [`RESET`](super::RESET) and a newline.
+/// The last row of an image with an odd height is drawn over the default background.
+///
+/// ```
+/// let red = (255, 0, 0);
+/// let blue = (0, 0, 255);
+/// let s = ansi_gfx::encode_image(&[red, blue], 1);
+/// assert_eq!(s, "\x1b[38;2;255;0;0;48;2;0;0;255m▀\x1b[0m\n");
+/// ```
+pub fn encode_image(pixels: &[(u8, u8, u8)], width: usize) -> String {
+	let mut s = String::new();
+	if width == 0 {
+		return s;
+	}
+	let rows: Vec<&[(u8, u8, u8)]> = pixels.chunks(width).collect();
+	for pair in rows.chunks(2) {
+		for (x, &(red, green, blue)) in pair[0].iter().enumerate() {
+			let _ = match pair.get(1).and_then(|bottom| bottom.get(x)) {
+				Some(&(r, g, b)) => write!(s, "{}▀", Print { __codes: [38, 2, red, green, blue, 48, 2, r, g, b] }),
+				None => write!(s, "{}▀", Print { __codes: [38, 2, red, green, blue, 49] }),
+			};
+		}
+		s.push_str("\x1b[0m\n");
+	}
+	s
+}
+
+/// Converts styled text to HTML.
+///
+/// The text is HTML escaped and every run of styled text is wrapped in a `<span>` with an inline CSS style.
+/// A span is closed and a new one opened whenever the style changes, so the spans are never nested and always balanced.
+/// Colors are converted to RGB with [`palette_to_rgb`], inverse swaps the colors which are set.
+/// Other escape sequences are dropped.
+///
+/// ```
+/// let mut html = String::new();
+/// ansi_gfx::to_html_doc(&format!("{}a<b{}c", ansi_gfx::mode!(BOLD; RED), ansi_gfx::RESET), &mut html);
+/// assert_eq!(html, "<span style=\"color:#aa0000;font-weight:bold\">a&lt;b</span>c");
+/// ```
+pub fn to_html_doc(input: &str, out: &mut String) {
+	let mut state = State::CLEARED;
+	let mut open = String::new();
+	let mut buf = alloc::vec![0u8; input.len()];
+	for event in events(input, &mut buf) {
+		match event {
+			Event::Style(print) => {
+				for segment in segments(print.__codes) {
+					state.apply(segment);
+				}
+				let css = css(&state);
+				if css != open {
+					if !open.is_empty() {
+						out.push_str("</span>");
+					}
+					if !css.is_empty() {
+						let _ = write!(out, "<span style=\"{}\">", css);
+					}
+					open = css;
+				}
+			},
+			Event::Text(text) => {
+				for c in text.chars() {
+					match c {
+						'&' => out.push_str("&amp;"),
+						'<' => out.push_str("&lt;"),
+						'>' => out.push_str("&gt;"),
+						'"' => out.push_str("&quot;"),
+						'\'' => out.push_str("&#39;"),
+						c => out.push(c),
+					}
+				}
+			},
+			Event::Other(_) => (),
+		}
+	}
+	if !open.is_empty() {
+		out.push_str("</span>");
+	}
+}
+
+/// Returns the inline CSS for the style of a terminal.
+fn css(state: &State) -> String {
+	fn rgb(color: Option<Segment>) -> Option<(u8, u8, u8)> {
+		match *color?.as_bytes() {
+			[n @ (30..=37 | 40..=47)] => Some(palette_to_rgb(n % 10)),
+			[n @ (90..=97 | 100..=107)] => Some(palette_to_rgb(n % 10 + 8)),
+			[38 | 48, 5, index] => Some(palette_to_rgb(index)),
+			[38 | 48, 2, red, green, blue] => Some((red, green, blue)),
+			_ => None,
+		}
+	}
+	let on = |code: u8| state.on & 1 << code != 0;
+	let (mut fg, mut bg) = (rgb(state.fg), rgb(state.bg));
+	if on(7) {
+		(fg, bg) = (bg, fg);
+	}
+	let mut css = String::new();
+	let mut declare = |property: fmt::Arguments| {
+		if !css.is_empty() {
+			css.push(';');
+		}
+		let _ = css.write_fmt(property);
+	};
+	if let Some((red, green, blue)) = fg {
+		declare(format_args!("color:#{:02x}{:02x}{:02x}", red, green, blue));
+	}
+	if let Some((red, green, blue)) = bg {
+		declare(format_args!("background-color:#{:02x}{:02x}{:02x}", red, green, blue));
+	}
+	if on(1) {
+		declare(format_args!("font-weight:bold"));
+	}
+	if on(2) {
+		declare(format_args!("opacity:0.5"));
+	}
+	if on(3) {
+		declare(format_args!("font-style:italic"));
+	}
+	match (on(4), on(9)) {
+		(true, true) => declare(format_args!("text-decoration:underline line-through")),
+		(true, false) => declare(format_args!("text-decoration:underline")),
+		(false, true) => declare(format_args!("text-decoration:line-through")),
+		(false, false) => (),
+	}
+	if on(8) {
+		declare(format_args!("visibility:hidden"));
+	}
+	css
+}
+
+/// Fluent builder for styled strings.
+///
+/// Every method appends an escape sequence or text.
+///
+/// ```
+/// let s = ansi_gfx::StyleString::new().bold().red().text("hi").reset();
+/// assert_eq!(s.as_str(), "\x1b[1m\x1b[31mhi\x1b[0m");
+/// ```
+#[derive(Clone, Default, Eq, PartialEq, Hash)]
+#[must_use]
+pub struct StyleString {
+	string: String,
+}
+
+macro_rules! style_methods {
+	($($name:ident => $code:ident,)*) => {
+		$(
+			#[doc = concat!("Appends [`", stringify!($code), "`](super::", stringify!($code), ").")]
+			#[inline]
+			pub fn $name(self) -> StyleString {
+				self.code(super::$code)
+			}
+		)*
+	};
+}
+
+impl StyleString {
+	/// Creates an empty styled string.
+	#[inline]
+	pub const fn new() -> StyleString {
+		StyleString { string: String::new() }
+	}
+
+	/// Appends a code.
+	#[inline]
+	pub fn code(self, code: Code) -> StyleString {
+		self.style(code)
+	}
+
+	/// Appends a style.
+	pub fn style(mut self, style: impl fmt::Display) -> StyleString {
+		let _ = write!(self.string, "{}", style);
+		self
+	}
+
+	/// Appends the foreground color.
+	#[inline]
+	pub fn fg(self, color: Color) -> StyleString {
+		self.style(color.fg())
+	}
+
+	/// Appends the background color.
+	#[inline]
+	pub fn bg(self, color: Color) -> StyleString {
+		self.style(color.bg())
+	}
+
+	/// Appends text.
+	#[inline]
+	pub fn text(mut self, text: &str) -> StyleString {
+		self.string.push_str(text);
+		self
+	}
+
+	style_methods! {
+		bold => BOLD,
+		dim => DIM,
+		italic => ITALIC,
+		underline => UNDERLINE,
+		blink => BLINK,
+		inverse => INVERSE,
+		hidden => HIDDEN,
+		strike => STRIKE,
+
+		reset => RESET,
+		reset_weight => RESET_WEIGHT,
+		reset_italic => RESET_ITALIC,
+		reset_underline => RESET_UNDERLINE,
+		reset_blink => RESET_BLINK,
+		reset_inverse => RESET_INVERSE,
+		reset_hidden => RESET_HIDDEN,
+		reset_strike => RESET_STRIKE,
+
+		black => BLACK,
+		red => RED,
+		green => GREEN,
+		yellow => YELLOW,
+		blue => BLUE,
+		magenta => MAGENTA,
+		cyan => CYAN,
+		white => WHITE,
+		default_fg => DEFAULT,
+
+		black_bg => BLACK_BG,
+		red_bg => RED_BG,
+		green_bg => GREEN_BG,
+		yellow_bg => YELLOW_BG,
+		blue_bg => BLUE_BG,
+		magenta_bg => MAGENTA_BG,
+		cyan_bg => CYAN_BG,
+		white_bg => WHITE_BG,
+		default_bg => DEFAULT_BG,
+
+		bright_black => BRIGHT_BLACK,
+		bright_red => BRIGHT_RED,
+		bright_green => BRIGHT_GREEN,
+		bright_yellow => BRIGHT_YELLOW,
+		bright_blue => BRIGHT_BLUE,
+		bright_magenta => BRIGHT_MAGENTA,
+		bright_cyan => BRIGHT_CYAN,
+		bright_white => BRIGHT_WHITE,
+
+		bright_black_bg => BRIGHT_BLACK_BG,
+		bright_red_bg => BRIGHT_RED_BG,
+		bright_green_bg => BRIGHT_GREEN_BG,
+		bright_yellow_bg => BRIGHT_YELLOW_BG,
+		bright_blue_bg => BRIGHT_BLUE_BG,
+		bright_magenta_bg => BRIGHT_MAGENTA_BG,
+		bright_cyan_bg => BRIGHT_CYAN_BG,
+		bright_white_bg => BRIGHT_WHITE_BG,
+	}
+
+	/// Returns the styled string.
+	#[inline]
+	pub fn as_str(&self) -> &str {
+		&self.string
+	}
+
+	/// Returns the styled string.
+	#[inline]
+	pub fn into_string(self) -> String {
+		self.string
+	}
+}
+
+impl fmt::Display for StyleString {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.write_str(&self.string)
+	}
+}
+
+impl fmt::Debug for StyleString {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		fmt::Debug::fmt(&self.string, f)
+	}
+}
+
+impl From<StyleString> for String {
+	#[inline]
+	fn from(s: StyleString) -> String {
+		s.string
+	}
+}
@@ -0,0 +1,146 @@
+use core::sync::atomic::{AtomicU8, Ordering};
+use super::{Code, Print, When};
+
+/// Whether to emit styles, eg. from a `--color=auto|always|never` option.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub enum ColorChoice {
+	/// Detect from the environment, see [`colors_enabled`].
+	#[default]
+	Auto,
+	/// Always emit styles.
+	Always,
+	/// Never emit styles.
+	Never,
+}
+
+static COLOR_CHOICE: AtomicU8 = AtomicU8::new(ColorChoice::Auto as u8);
+
+/// Sets the process-global color choice.
+///
+/// The choice is stored in an atomic and can be changed from any thread at any time,
+/// styles already being formatted on other threads may still use the previous choice.
+/// Applications typically set it once at startup after parsing their arguments.
+#[inline]
+pub fn set_color_choice(choice: ColorChoice) {
+	COLOR_CHOICE.store(choice as u8, Ordering::Relaxed);
+}
+
+/// Returns the process-global color choice.
+///
+/// Defaults to [`ColorChoice::Auto`].
+#[inline]
+pub fn color_choice() -> ColorChoice {
+	match COLOR_CHOICE.load(Ordering::Relaxed) {
+		1 => ColorChoice::Always,
+		2 => ColorChoice::Never,
+		_ => ColorChoice::Auto,
+	}
+}
+
+/// Returns true if styles should be emitted according to the global [`color_choice`].
+///
+/// With [`ColorChoice::Auto`] colors are disabled if the `NO_COLOR` environment variable is set to a non-empty value or `TERM` is `dumb`.
+/// The environment is read on the first call and cached.
+/// Without the `std` feature the environment cannot be inspected and `Auto` enables colors.
+pub fn colors_enabled() -> bool {
+	match color_choice() {
+		ColorChoice::Always => true,
+		ColorChoice::Never => false,
+		ColorChoice::Auto => env_colors_enabled(),
+	}
+}
+
+/// Reads the environment once, the result is cached like the terminal detection.
+#[cfg(feature = "std")]
+pub(crate) fn env_colors_enabled() -> bool {
+	ENV.get(|| {
+		let no_color = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+		let dumb = std::env::var_os("TERM").is_some_and(|term| term == "dumb");
+		!no_color && !dumb
+	})
+}
+
+/// Cached detection result, 0 when not yet detected.
+#[cfg(feature = "std")]
+struct Detected(AtomicU8);
+
+#[cfg(feature = "std")]
+impl Detected {
+	fn get(&self, detect: impl FnOnce() -> bool) -> bool {
+		match self.0.load(Ordering::Relaxed) {
+			1 => false,
+			2 => true,
+			_ => {
+				let enabled = detect();
+				self.0.store(if enabled { 2 } else { 1 }, Ordering::Relaxed);
+				enabled
+			},
+		}
+	}
+}
+
+#[cfg(feature = "std")]
+static ENV: Detected = Detected(AtomicU8::new(0));
+#[cfg(feature = "std")]
+static STDOUT: Detected = Detected(AtomicU8::new(0));
+#[cfg(feature = "std")]
+static STDERR: Detected = Detected(AtomicU8::new(0));
+
+#[cfg(feature = "std")]
+pub(crate) fn tty_colors_enabled(is_terminal: bool) -> bool {
+	is_terminal && env_colors_enabled()
+}
+
+/// Returns true if styles should be emitted to stdout.
+///
+/// With [`ColorChoice::Auto`] colors are enabled if stdout is a terminal and the environment allows colors (see [`colors_enabled`]).
+/// Styles are suppressed when the output is redirected to a file or pipe.
+/// The detection is done once and cached, the global [`color_choice`] is consulted on every call.
+#[cfg(feature = "std")]
+pub fn stdout_colors_enabled() -> bool {
+	use std::io::IsTerminal;
+	match color_choice() {
+		ColorChoice::Always => true,
+		ColorChoice::Never => false,
+		ColorChoice::Auto => STDOUT.get(|| tty_colors_enabled(std::io::stdout().is_terminal())),
+	}
+}
+
+/// Returns true if styles should be emitted to stderr.
+///
+/// Same as [`stdout_colors_enabled`] for stderr.
+#[cfg(feature = "std")]
+pub fn stderr_colors_enabled() -> bool {
+	use std::io::IsTerminal;
+	match color_choice() {
+		ColorChoice::Always => true,
+		ColorChoice::Never => false,
+		ColorChoice::Auto => STDERR.get(|| tty_colors_enabled(std::io::stderr().is_terminal())),
+	}
+}
+
+#[cfg(not(feature = "std"))]
+pub(crate) fn env_colors_enabled() -> bool {
+	true
+}
+
+impl Code {
+	/// Emits the code only if [`colors_enabled`].
+	///
+	/// ```
+	/// ansi_gfx::set_color_choice(ansi_gfx::ColorChoice::Never);
+	/// assert_eq!(format!("{}", ansi_gfx::RED.gated().paint("error")), "error");
+	/// ```
+	#[inline]
+	pub fn gated(self) -> When<Code> {
+		self.when(colors_enabled())
+	}
+}
+
+impl<T: AsRef<[u8]>> Print<T> {
+	/// Emits the style only if [`colors_enabled`].
+	#[inline]
+	pub fn gated(self) -> When<Print<T>> {
+		self.when(colors_enabled())
+	}
+}
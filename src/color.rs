@@ -0,0 +1,152 @@
+use core::fmt;
+use super::{write_codes, Code, Print};
+
+/// Extracts the channels of a color packed as `0xRRGGBB`.
+///
+/// The high byte is ignored.
+///
+/// ```
+/// assert_eq!(ansi_gfx::rgb_u32(0xF39F18), (0xF3, 0x9F, 0x18));
+/// assert_eq!(format!("{}", ansi_gfx::mode!(FG HEX 0xFF0000)), "\x1b[38;2;255;0;0m");
+/// ```
+#[inline]
+pub const fn rgb_u32(packed: u32) -> (u8, u8, u8) {
+	((packed >> 16) as u8, (packed >> 8) as u8, packed as u8)
+}
+
+/// Returns the foreground style for an RGB color stored as `[u8; 3]`.
+///
+/// Many image and color libraries store colors this way, also available as `FG ARR` in [`mode!`](crate::mode!).
+///
+/// ```
+/// assert_eq!(format!("{}", ansi_gfx::fg_rgb_arr([255, 0, 0])), "\x1b[38;2;255;0;0m");
+/// assert_eq!(format!("{}", ansi_gfx::mode!(BG ARR [0, 0, 255])), "\x1b[48;2;0;0;255m");
+/// ```
+#[inline]
+pub const fn fg_rgb_arr(rgb: [u8; 3]) -> Print<[u8; 5]> {
+	Print { __codes: [38, 2, rgb[0], rgb[1], rgb[2]] }
+}
+
+/// Returns the background style for an RGB color stored as `[u8; 3]`.
+///
+/// See [`fg_rgb_arr`].
+#[inline]
+pub const fn bg_rgb_arr(rgb: [u8; 3]) -> Print<[u8; 5]> {
+	Print { __codes: [48, 2, rgb[0], rgb[1], rgb[2]] }
+}
+
+/// Logical color.
+///
+/// Formats as the foreground escape sequence, use [`Color::bg`] for the background escape sequence.
+///
+/// ```
+/// use ansi_gfx::Color;
+/// assert_eq!(format!("{}", Color::Named(ansi_gfx::RED)), "\x1b[31m");
+/// assert_eq!(format!("{}", Color::Palette(28).bg()), "\x1b[48;5;28m");
+/// assert_eq!(format!("{}", Color::Rgb(243, 159, 24)), "\x1b[38;2;243;159;24m");
+/// ```
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+#[must_use]
+pub enum Color {
+	/// Named color, eg. [`RED`](super::RED) or [`BRIGHT_BLUE_BG`](super::BRIGHT_BLUE_BG).
+	///
+	/// Both the foreground and background codes are accepted, the color is converted as needed.
+	Named(Code),
+	/// 256-color palette index.
+	Palette(u8),
+	/// True color.
+	Rgb(u8, u8, u8),
+}
+
+impl Color {
+	/// Returns the codes for the foreground or background variant.
+	pub(crate) fn codes(self, bg: bool) -> ColorCodes {
+		let ground = if bg { 48 } else { 38 };
+		match self {
+			Color::Named(code) => {
+				let byte = match code.__byte {
+					byte @ (30..=39 | 90..=97) if bg => byte + 10,
+					byte @ (40..=49 | 100..=107) if !bg => byte - 10,
+					byte => byte,
+				};
+				ColorCodes { bytes: [byte, 0, 0, 0, 0], len: 1 }
+			},
+			Color::Palette(index) => ColorCodes { bytes: [ground, 5, index, 0, 0], len: 3 },
+			Color::Rgb(red, green, blue) => ColorCodes { bytes: [ground, 2, red, green, blue], len: 5 },
+		}
+	}
+
+	/// Returns the foreground style.
+	#[inline]
+	pub fn fg(self) -> Print<ColorCodes> {
+		Print { __codes: self.codes(false) }
+	}
+
+	/// Returns the background style.
+	#[inline]
+	pub fn bg(self) -> Print<ColorCodes> {
+		Print { __codes: self.codes(true) }
+	}
+}
+
+impl fmt::Display for Color {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write_codes(self.codes(false).as_ref(), f)
+	}
+}
+
+impl From<Code> for Color {
+	/// Converts to a named color.
+	///
+	/// The code is not checked, see [`Color::Named`].
+	#[inline]
+	fn from(code: Code) -> Color {
+		Color::Named(code)
+	}
+}
+
+impl From<u8> for Color {
+	/// Converts to a palette color.
+	#[inline]
+	fn from(index: u8) -> Color {
+		Color::Palette(index)
+	}
+}
+
+impl From<(u8, u8, u8)> for Color {
+	/// Converts to a true color.
+	#[inline]
+	fn from((red, green, blue): (u8, u8, u8)) -> Color {
+		Color::Rgb(red, green, blue)
+	}
+}
+
+impl From<[u8; 3]> for Color {
+	/// Converts to a true color.
+	#[inline]
+	fn from([red, green, blue]: [u8; 3]) -> Color {
+		Color::Rgb(red, green, blue)
+	}
+}
+
+impl From<Color> for Print<ColorCodes> {
+	/// Converts to the foreground style.
+	#[inline]
+	fn from(color: Color) -> Print<ColorCodes> {
+		color.fg()
+	}
+}
+
+/// Codes of a single color.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct ColorCodes {
+	bytes: [u8; 5],
+	len: u8,
+}
+
+impl AsRef<[u8]> for ColorCodes {
+	#[inline]
+	fn as_ref(&self) -> &[u8] {
+		&self.bytes[..self.len as usize]
+	}
+}
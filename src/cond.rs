@@ -0,0 +1,103 @@
+use core::fmt;
+use super::{Code, Print, RESET};
+
+impl Code {
+	/// Emits the code only if the condition is true.
+	///
+	/// ```
+	/// let is_error = true;
+	/// let s = format!("{}", ansi_gfx::RED.when(is_error).paint("failed"));
+	/// assert_eq!(s, "\x1b[31mfailed\x1b[0m");
+	/// assert_eq!(format!("{}", ansi_gfx::RED.when(false)), "");
+	/// ```
+	#[inline]
+	pub fn when(self, cond: bool) -> When<Code> {
+		When { style: self, cond }
+	}
+}
+
+impl<T: AsRef<[u8]>> Print<T> {
+	/// Emits the style only if the condition is true.
+	///
+	/// ```
+	/// let style = ansi_gfx::mode!(BOLD; RED);
+	/// assert_eq!(format!("{}", style.when(true)), "\x1b[1;31m");
+	/// assert_eq!(format!("{}", style.when(false).paint("ok")), "ok");
+	/// ```
+	#[inline]
+	pub fn when(self, cond: bool) -> When<Print<T>> {
+		When { style: self, cond }
+	}
+}
+
+/// Display adapter returned by [`Code::when`] and [`Print::when`].
+#[derive(Copy, Clone, Debug)]
+#[must_use]
+pub struct When<S> {
+	style: S,
+	cond: bool,
+}
+
+impl<S: fmt::Display> When<S> {
+	/// Formats the content in the style followed by a [`RESET`] if the condition is true, otherwise the content is formatted as is.
+	#[inline]
+	pub fn paint<D: fmt::Display>(self, content: D) -> Paint<S, D> {
+		Paint { when: self, content }
+	}
+}
+
+impl<S: fmt::Display> fmt::Display for When<S> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		if self.cond {
+			self.style.fmt(f)?;
+		}
+		Ok(())
+	}
+}
+
+/// Display adapter returned by [`When::paint`].
+#[derive(Copy, Clone, Debug)]
+#[must_use]
+pub struct Paint<S, D> {
+	when: When<S>,
+	content: D,
+}
+
+impl<S: fmt::Display, D: fmt::Display> fmt::Display for Paint<S, D> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		if self.when.cond {
+			write!(f, "{}{}{}", self.when.style, self.content, RESET)
+		}
+		else {
+			self.content.fmt(f)
+		}
+	}
+}
+
+/// Emits an optional style, nothing if it is `None`.
+///
+/// ```
+/// use ansi_gfx::Maybe;
+/// let highlight = Some(ansi_gfx::RED);
+/// assert_eq!(format!("{}", Maybe(highlight)), "\x1b[31m");
+/// assert_eq!(format!("{}", Maybe::<ansi_gfx::Code>(None)), "");
+/// ```
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+#[must_use]
+pub struct Maybe<S>(pub Option<S>);
+
+impl<S> From<Option<S>> for Maybe<S> {
+	#[inline]
+	fn from(style: Option<S>) -> Maybe<S> {
+		Maybe(style)
+	}
+}
+
+impl<S: fmt::Display> fmt::Display for Maybe<S> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match &self.0 {
+			Some(style) => style.fmt(f),
+			None => Ok(()),
+		}
+	}
+}
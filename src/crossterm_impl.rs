@@ -0,0 +1,83 @@
+use std::{error, fmt};
+use crossterm::style::{Attribute, Color};
+use super::Code;
+
+const NAMED: [Color; 8] = [Color::Black, Color::DarkRed, Color::DarkGreen, Color::DarkYellow, Color::DarkBlue, Color::DarkMagenta, Color::DarkCyan, Color::Grey];
+const BRIGHT: [Color; 8] = [Color::DarkGrey, Color::Red, Color::Green, Color::Yellow, Color::Blue, Color::Magenta, Color::Cyan, Color::White];
+
+/// Error returned when a code has no `crossterm` equivalent.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct UnsupportedCode(pub Code);
+
+impl fmt::Display for UnsupportedCode {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "code {} has no crossterm equivalent", self.0.__byte)
+	}
+}
+
+impl error::Error for UnsupportedCode {}
+
+impl TryFrom<Code> for Color {
+	type Error = UnsupportedCode;
+
+	/// Converts a named foreground or background color.
+	///
+	/// Note that `crossterm` names the basic colors `Dark*` and the bright colors without prefix.
+	///
+	/// ```
+	/// use crossterm::style::Color;
+	/// assert_eq!(Color::try_from(ansi_gfx::RED), Ok(Color::DarkRed));
+	/// assert_eq!(Color::try_from(ansi_gfx::BRIGHT_RED_BG), Ok(Color::Red));
+	/// assert!(Color::try_from(ansi_gfx::BOLD).is_err());
+	/// ```
+	fn try_from(code: Code) -> Result<Color, UnsupportedCode> {
+		match code.__byte {
+			byte @ (30..=37 | 40..=47) => Ok(NAMED[(byte % 10) as usize]),
+			byte @ (90..=97 | 100..=107) => Ok(BRIGHT[(byte % 10) as usize]),
+			39 | 49 => Ok(Color::Reset),
+			_ => Err(UnsupportedCode(code)),
+		}
+	}
+}
+
+impl TryFrom<Code> for Attribute {
+	type Error = UnsupportedCode;
+
+	/// Converts an attribute or the full reset.
+	///
+	/// ```
+	/// use crossterm::style::Attribute;
+	/// assert_eq!(Attribute::try_from(ansi_gfx::BOLD), Ok(Attribute::Bold));
+	/// assert!(Attribute::try_from(ansi_gfx::RED).is_err());
+	/// ```
+	fn try_from(code: Code) -> Result<Attribute, UnsupportedCode> {
+		let attr = match code.__byte {
+			0 => Attribute::Reset,
+			1 => Attribute::Bold,
+			2 => Attribute::Dim,
+			3 => Attribute::Italic,
+			4 => Attribute::Underlined,
+			5 => Attribute::SlowBlink,
+			6 => Attribute::RapidBlink,
+			7 => Attribute::Reverse,
+			8 => Attribute::Hidden,
+			9 => Attribute::CrossedOut,
+			20 => Attribute::Fraktur,
+			21 => Attribute::NoBold,
+			22 => Attribute::NormalIntensity,
+			23 => Attribute::NoItalic,
+			24 => Attribute::NoUnderline,
+			25 => Attribute::NoBlink,
+			27 => Attribute::NoReverse,
+			28 => Attribute::NoHidden,
+			29 => Attribute::NotCrossedOut,
+			51 => Attribute::Framed,
+			52 => Attribute::Encircled,
+			53 => Attribute::OverLined,
+			54 => Attribute::NotFramedOrEncircled,
+			55 => Attribute::NotOverLined,
+			_ => return Err(UnsupportedCode(code)),
+		};
+		Ok(attr)
+	}
+}
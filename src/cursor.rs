@@ -0,0 +1,166 @@
+/*!
+Cursor positioning.
+
+```
+use ansi_gfx::cursor::Goto;
+assert_eq!(format!("{}", Goto::new(3, 10)), "\x1b[3;10H");
+```
+*/
+
+use core::fmt::{self, Write};
+use super::{control_len, sgr_sequence_len, write_params, BufWriter};
+use super::state::State;
+
+/// Moves the cursor to a position.
+///
+/// Rows and columns are 1-based as in the ANSI specification, the top left corner is `(1, 1)`.
+/// Terminals disagree on what a 0 coordinate means, it is clamped to 1.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+#[must_use]
+pub struct Goto {
+	row: u16,
+	col: u16,
+}
+
+impl Goto {
+	/// Creates a cursor position from 1-based coordinates.
+	///
+	/// ```
+	/// let goto = ansi_gfx::cursor::Goto::new(0, 0);
+	/// assert_eq!(format!("{}", goto), "\x1b[1;1H");
+	/// assert_eq!((goto.row(), goto.col()), (1, 1));
+	/// ```
+	#[inline]
+	pub const fn new(row: u16, col: u16) -> Goto {
+		Goto {
+			row: if row == 0 { 1 } else { row },
+			col: if col == 0 { 1 } else { col },
+		}
+	}
+
+	/// Returns the 1-based row.
+	#[inline]
+	pub const fn row(&self) -> u16 {
+		self.row
+	}
+
+	/// Returns the 1-based column.
+	#[inline]
+	pub const fn col(&self) -> u16 {
+		self.col
+	}
+}
+
+impl fmt::Display for Goto {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.write_str("\x1b[")?;
+		write_params(&[self.row, self.col], b'H', f)
+	}
+}
+
+/// Character cell of a frame.
+enum Cell {
+	Char(char, State),
+	Newline,
+}
+
+/// Iterates over the cells of a frame, tracking the style across lines.
+struct Cells<'a> {
+	s: &'a str,
+	state: State,
+}
+
+impl<'a> Cells<'a> {
+	fn peek(&mut self) -> Option<Cell> {
+		while self.s.starts_with('\x1b') {
+			let len = match sgr_sequence_len(self.s) {
+				Some(len) => {
+					self.state.apply_sequence(&self.s[..len]);
+					len
+				},
+				// Other escape sequences take no cells
+				None => {
+					let mut len = control_len(self.s.as_bytes());
+					while !self.s.is_char_boundary(len) {
+						len += 1;
+					}
+					len
+				},
+			};
+			self.s = &self.s[len..];
+		}
+		match self.s.chars().next()? {
+			'\n' => Some(Cell::Newline),
+			c => Some(Cell::Char(c, self.state)),
+		}
+	}
+	fn advance(&mut self) {
+		if let Some(c) = self.s.chars().next() {
+			self.s = &self.s[c.len_utf8()..];
+		}
+	}
+}
+
+/// Encodes the changes between two frames of an animation.
+///
+/// Both frames are full screens of styled text starting at the top left corner, with lines separated by `\n`.
+/// Only the cells which changed their character or style are written, each run of changed cells is preceded by a [`Goto`] and the style is set as needed.
+/// A [`RESET`](super::RESET) follows the update if a style was set.
+/// Identical frames produce an empty update.
+///
+/// The frames should have the same dimensions, cells beyond the end of a line in the previous frame are not cleared.
+/// Cells are counted as `char`s, not terminal columns.
+/// Escape sequences other than SGR escape sequences take no cells and are not written.
+/// Returns `None` if the buffer is too small.
+///
+/// ```
+/// let mut buf = [0u8; 32];
+/// let update = ansi_gfx::cursor::frame_diff("ab\ncd", "ab\nxd", &mut buf);
+/// assert_eq!(update, Some("\x1b[2;1Hx"));
+/// ```
+pub fn frame_diff<'a>(prev: &str, next: &str, out: &'a mut [u8]) -> Option<&'a str> {
+	let mut writer = BufWriter::new(out);
+	let mut prev = Cells { s: prev, state: State::CLEARED };
+	let mut next = Cells { s: next, state: State::CLEARED };
+	// Position and style of the terminal after the last write
+	let mut cursor = None;
+	let mut style = State::CLEARED;
+	let (mut row, mut col) = (1u16, 1u16);
+	while let Some(cell) = next.peek() {
+		next.advance();
+		match cell {
+			Cell::Newline => {
+				// Skip what remains of the previous frame's line
+				while let Some(Cell::Char(..)) = prev.peek() {
+					prev.advance();
+				}
+				prev.advance();
+				row = row.saturating_add(1);
+				col = 1;
+			},
+			Cell::Char(c, state) => {
+				let old = prev.peek();
+				if let Some(Cell::Char(..)) = old {
+					prev.advance();
+				}
+				let same = matches!(old, Some(Cell::Char(old, old_state)) if old == c && old_state == state);
+				if !same {
+					if cursor != Some((row, col)) {
+						write!(writer, "{}", Goto::new(row, col)).ok()?;
+					}
+					if style != state {
+						state.write_fresh(&mut writer).ok()?;
+						style = state;
+					}
+					writer.write_char(c).ok()?;
+					cursor = Some((row, col.saturating_add(1)));
+				}
+				col = col.saturating_add(1);
+			},
+		}
+	}
+	if !style.is_cleared() {
+		writer.write_str("\x1b[0m").ok()?;
+	}
+	Some(writer.into_str())
+}
@@ -0,0 +1,52 @@
+use core::fmt;
+use super::{write_codes, Code, Print};
+
+/// Fixed capacity style built at runtime without `alloc`.
+///
+/// ```
+/// let mut style = ansi_gfx::PrintVec::<4>::new();
+/// style.push(ansi_gfx::BOLD).unwrap();
+/// style.push(ansi_gfx::RED).unwrap();
+/// assert_eq!(format!("{}", style), "\x1b[1;31m");
+/// ```
+#[derive(Clone, Default, Eq, PartialEq, Hash)]
+#[must_use]
+pub struct PrintVec<const N: usize> {
+	codes: heapless::Vec<u8, N>,
+}
+
+impl<const N: usize> PrintVec<N> {
+	/// Creates an empty style.
+	#[inline]
+	pub const fn new() -> PrintVec<N> {
+		PrintVec { codes: heapless::Vec::new() }
+	}
+
+	/// Appends a code.
+	///
+	/// Returns the code back if the style is full.
+	#[inline]
+	pub fn push(&mut self, code: Code) -> Result<(), Code> {
+		self.codes.push(code.__byte).map_err(|byte| Code { __byte: byte })
+	}
+
+	/// Returns the style.
+	#[inline]
+	pub fn as_print(&self) -> Print<&[u8]> {
+		Print { __codes: &self.codes }
+	}
+}
+
+impl<const N: usize> fmt::Display for PrintVec<N> {
+	#[inline]
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write_codes(&self.codes, f)
+	}
+}
+
+impl<const N: usize> fmt::Debug for PrintVec<N> {
+	#[inline]
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		fmt::Debug::fmt(&self.as_print(), f)
+	}
+}
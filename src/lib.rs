@@ -40,10 +40,14 @@ println!(
 
 */
 
-#![cfg_attr(not(test), no_std)]
+#![cfg_attr(not(any(test, feature = "std")), no_std)]
+#![allow(clippy::tabs_in_doc_comments)]
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
 
-use core::{fmt, slice, str};
+
+use core::{fmt, ops, slice, str};
 
 /// ANSI graphics mode builder for complex codes.
 ///
@@ -56,8 +60,13 @@ use core::{fmt, slice, str};
 /// * A runtime [`Code`] value (e.g. `{ansi_gfx::BOLD}`).
 /// * A foreground palette color (e.g. `FG PAL 28`).
 /// * A background palette color (e.g. `BG PAL 28`).
+///   See [`palette_kind`](palette::palette_kind) for what each palette index renders as.
 /// * A foreground RGB color (e.g. `FG RGB 255, 0, 0`).
 /// * A background RGB color (e.g. `BG RGB 255, 0, 0`).
+/// * A foreground or background RGB color packed as `0xRRGGBB` (e.g. `FG HEX 0xF39F18`).
+///   The expression is evaluated once, see [`rgb_u32`].
+/// * A foreground or background RGB color from a `[u8; 3]` array (e.g. `FG ARR [255, 0, 0]`).
+///   The expression is evaluated once, see [`fg_rgb_arr`].
 ///
 /// # Examples
 ///
@@ -71,36 +80,135 @@ use core::{fmt, slice, str};
 /// <pre style="background-color: black; color: lightgray;"><span style="font-weight: bold; text-decoration: underline;">Bold and underlined</span>
 /// <span style="color: rgb(239, 41, 41); background-color: rgb(252, 233, 79);">Red on yellow</span>
 /// <span style="background-color: lightgray; color: black;">Inverted</span></pre>
+///
+/// A single invocation is limited to 255 codes, exceeding this is a compile error:
+///
+/// ```compile_fail
+/// // 52 × 5 = 260 codes
+/// let _ = ansi_gfx::mode!(
+/// 	FG RGB 1, 2, 3; FG RGB 1, 2, 3; FG RGB 1, 2, 3; FG RGB 1, 2, 3;
+/// 	FG RGB 1, 2, 3; FG RGB 1, 2, 3; FG RGB 1, 2, 3; FG RGB 1, 2, 3;
+/// 	FG RGB 1, 2, 3; FG RGB 1, 2, 3; FG RGB 1, 2, 3; FG RGB 1, 2, 3;
+/// 	FG RGB 1, 2, 3; FG RGB 1, 2, 3; FG RGB 1, 2, 3; FG RGB 1, 2, 3;
+/// 	FG RGB 1, 2, 3; FG RGB 1, 2, 3; FG RGB 1, 2, 3; FG RGB 1, 2, 3;
+/// 	FG RGB 1, 2, 3; FG RGB 1, 2, 3; FG RGB 1, 2, 3; FG RGB 1, 2, 3;
+/// 	FG RGB 1, 2, 3; FG RGB 1, 2, 3; FG RGB 1, 2, 3; FG RGB 1, 2, 3;
+/// 	FG RGB 1, 2, 3; FG RGB 1, 2, 3; FG RGB 1, 2, 3; FG RGB 1, 2, 3;
+/// 	FG RGB 1, 2, 3; FG RGB 1, 2, 3; FG RGB 1, 2, 3; FG RGB 1, 2, 3;
+/// 	FG RGB 1, 2, 3; FG RGB 1, 2, 3; FG RGB 1, 2, 3; FG RGB 1, 2, 3;
+/// 	FG RGB 1, 2, 3; FG RGB 1, 2, 3; FG RGB 1, 2, 3; FG RGB 1, 2, 3;
+/// 	FG RGB 1, 2, 3; FG RGB 1, 2, 3; FG RGB 1, 2, 3; FG RGB 1, 2, 3;
+/// 	FG RGB 1, 2, 3; FG RGB 1, 2, 3; FG RGB 1, 2, 3; FG RGB 1, 2, 3);
+/// ```
 #[macro_export]
 macro_rules! mode {
 	($($tt:tt)*) => {
-		$crate::__mode!([] $($tt)*)
+		$crate::__mode!([] {} $($tt)*)
+	};
+}
+
+/// Same as [`mode!`] but starts with a [`RESET`].
+///
+/// The style does not inherit anything from what was formatted before it.
+/// See [`Print::fresh`] to do the same at runtime.
+///
+/// ```
+/// assert_eq!(format!("{}", ansi_gfx::mode_fresh!(BOLD)), "\x1b[0;1m");
+/// ```
+#[macro_export]
+macro_rules! mode_fresh {
+	($($tt:tt)*) => {
+		$crate::__mode!([$crate::codes::RESET.__byte,] {} $($tt)*)
+	};
+}
+
+/// Defines a named style constant.
+///
+/// Expands to a `const` of type `Print<[u8; N]>` initialized with [`mode!`], with the array length worked out for you.
+/// Attributes and visibility are forwarded.
+///
+/// ```
+/// ansi_gfx::style! {
+/// 	/// Style for warnings.
+/// 	pub WARNING = BOLD; FG PAL 214
+/// }
+/// assert_eq!(format!("{}", WARNING), format!("{}", ansi_gfx::mode!(BOLD; FG PAL 214)));
+/// ```
+#[macro_export]
+macro_rules! style {
+	($(#[$meta:meta])* $vis:vis $name:ident = $($tt:tt)*) => {
+		$(#[$meta])*
+		$vis const $name: $crate::Print<[u8; $crate::mode!($($tt)*).__codes.len()]> = $crate::mode!($($tt)*);
 	};
 }
 
 #[doc(hidden)]
 #[macro_export]
 macro_rules! __mode {
+	// Packed RGB
+	([$($code:expr,)*] {$($bind:tt)*} $ground:ident HEX $packed:expr; $($tail:tt)*) => {
+		$crate::__mode!([
+			$($code,)*
+			$crate::__FG_or_BG::$ground.__byte,
+			$crate::__RGB_or_PAL::RGB.__byte,
+			rgb.0,
+			rgb.1,
+			rgb.2,
+		] {$($bind)* let rgb: (u8, u8, u8) = $crate::rgb_u32($packed);} $($tail)*)
+	};
+	([$($code:expr,)*] {$($bind:tt)*} $ground:ident HEX $packed:expr) => {
+		$crate::__mode!([
+			$($code,)*
+			$crate::__FG_or_BG::$ground.__byte,
+			$crate::__RGB_or_PAL::RGB.__byte,
+			rgb.0,
+			rgb.1,
+			rgb.2,
+		] {$($bind)* let rgb: (u8, u8, u8) = $crate::rgb_u32($packed);})
+	};
+
+	// RGB array
+	([$($code:expr,)*] {$($bind:tt)*} $ground:ident ARR $rgb:expr; $($tail:tt)*) => {
+		$crate::__mode!([
+			$($code,)*
+			$crate::__FG_or_BG::$ground.__byte,
+			$crate::__RGB_or_PAL::RGB.__byte,
+			rgb[0],
+			rgb[1],
+			rgb[2],
+		] {$($bind)* let rgb: [u8; 3] = $rgb;} $($tail)*)
+	};
+	([$($code:expr,)*] {$($bind:tt)*} $ground:ident ARR $rgb:expr) => {
+		$crate::__mode!([
+			$($code,)*
+			$crate::__FG_or_BG::$ground.__byte,
+			$crate::__RGB_or_PAL::RGB.__byte,
+			rgb[0],
+			rgb[1],
+			rgb[2],
+		] {$($bind)* let rgb: [u8; 3] = $rgb;})
+	};
+
 	// Palette
-	([$($code:expr,)*] $ground:ident $space:ident $index:expr; $($tail:tt)*) => {
+	([$($code:expr,)*] {$($bind:tt)*} $ground:ident $space:ident $index:expr; $($tail:tt)*) => {
 		$crate::__mode!([
 			$($code,)*
 			$crate::__FG_or_BG::$ground.__byte,
 			$crate::__RGB_or_PAL::$space.__byte,
 			{ let index: u8 = $index; index },
-		] $($tail)*)
+		] {$($bind)*} $($tail)*)
 	};
-	([$($code:expr,)*] $ground:ident $space:ident $index:expr) => {
+	([$($code:expr,)*] {$($bind:tt)*} $ground:ident $space:ident $index:expr) => {
 		$crate::__mode!([
 			$($code,)*
 			$crate::__FG_or_BG::$ground.__byte,
 			$crate::__RGB_or_PAL::$space.__byte,
 			{ let index: u8 = $index; index },
-		])
+		] {$($bind)*})
 	};
 
 	// RGB
-	([$($code:expr,)*] $ground:ident $space:ident $red:expr, $green:expr, $blue:expr; $($tail:tt)*) => {
+	([$($code:expr,)*] {$($bind:tt)*} $ground:ident $space:ident $red:expr, $green:expr, $blue:expr; $($tail:tt)*) => {
 		$crate::__mode!([
 			$($code,)*
 			$crate::__FG_or_BG::$ground.__byte,
@@ -108,9 +216,9 @@ macro_rules! __mode {
 			{ let red: u8 = $red; red },
 			{ let green: u8 = $green; green },
 			{ let blue: u8 = $blue; blue },
-		] $($tail)*)
+		] {$($bind)*} $($tail)*)
 	};
-	([$($code:expr,)*] $ground:ident $space:ident $red:expr, $green:expr, $blue:expr) => {
+	([$($code:expr,)*] {$($bind:tt)*} $ground:ident $space:ident $red:expr, $green:expr, $blue:expr) => {
 		$crate::__mode!([
 			$($code,)*
 			$crate::__FG_or_BG::$ground.__byte,
@@ -118,45 +226,59 @@ macro_rules! __mode {
 			{ let red: u8 = $red; red },
 			{ let green: u8 = $green; green },
 			{ let blue: u8 = $blue; blue },
-		])
+		] {$($bind)*})
 	};
 
 	// Identifier
-	([$($code:expr,)*] $name:ident; $($tail:tt)*) => {
+	([$($code:expr,)*] {$($bind:tt)*} $name:ident; $($tail:tt)*) => {
 		$crate::__mode!([
 			$($code,)*
 			$crate::codes::$name.__byte,
-		] $($tail)*)
+		] {$($bind)*} $($tail)*)
 	};
-	([$($code:expr,)*] $name:ident) => {
+	([$($code:expr,)*] {$($bind:tt)*} $name:ident) => {
 		$crate::__mode!([
 			$($code,)*
 			$crate::codes::$name.__byte,
-		])
+		] {$($bind)*})
 	};
 
 	// Runtime value
-	([$($code:expr,)*] {$v:expr}; $($tail:tt)*) => {
+	([$($code:expr,)*] {$($bind:tt)*} {$v:expr}; $($tail:tt)*) => {
 		$crate::__mode!([
 			$($code,)*
 			{ let v: $crate::Code = $v; v.__byte },
-		] $($tail)*)
+		] {$($bind)*} $($tail)*)
 	};
-	([$($code:expr,)*] {$v:expr}) => {
+	([$($code:expr,)*] {$($bind:tt)*} {$v:expr}) => {
 		$crate::__mode!([
 			$($code,)*
 			{ let v: $crate::Code = $v; v.__byte },
-		])
+		] {$($bind)*})
 	};
 
 	// Term
-	([$($code:expr,)*]) => {
-		$crate::Print { __codes: [$($code),*] }
+	([$($code:expr,)*] {$($bind:tt)*}) => {
+		$crate::Print { __codes: {
+			// Colors used by several codes are evaluated once up front
+			$($bind)*
+			const { ::core::assert!(<[()]>::len(&[$($crate::__unit!($code)),*]) <= 255, "too many codes in mode!") };
+			[$($code),*]
+		} }
 	};
 }
 
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __unit {
+	($code:expr) => { () };
+}
+
 /// Single ANSI graphics code.
-#[derive(Copy, Clone, Eq, PartialEq)]
+///
+/// Codes do nothing unless formatted, dropping one is most likely a mistake and is warned about.
+#[derive(Copy, Clone, Eq, PartialEq, Hash)]
+#[must_use]
 #[repr(transparent)]
 pub struct Code {
 	#[doc(hidden)]
@@ -300,46 +422,901 @@ pub mod codes {
 
 pub use self::codes::*;
 
+/// Returns every code constant in [`codes`], in declaration order.
+///
+/// Useful for building selection menus or checking properties of all codes.
+///
+/// ```
+/// let names: Vec<_> = ansi_gfx::all_codes().iter().filter_map(|code| code.name()).collect();
+/// assert_eq!(names[0], "BOLD");
+/// assert_eq!(names.len(), ansi_gfx::all_codes().len());
+/// ```
+#[inline]
+pub fn all_codes() -> &'static [Code] {
+	const ALL_CODES: [Code; 50] = [
+		BOLD, DIM, ITALIC, UNDERLINE, BLINK, INVERSE, HIDDEN, STRIKE,
+		RESET, RESET_WEIGHT, RESET_ITALIC, RESET_UNDERLINE, RESET_BLINK, RESET_INVERSE, RESET_HIDDEN, RESET_STRIKE,
+		BLACK, RED, GREEN, YELLOW, BLUE, MAGENTA, CYAN, WHITE, DEFAULT,
+		BLACK_BG, RED_BG, GREEN_BG, YELLOW_BG, BLUE_BG, MAGENTA_BG, CYAN_BG, WHITE_BG, DEFAULT_BG,
+		BRIGHT_BLACK, BRIGHT_RED, BRIGHT_GREEN, BRIGHT_YELLOW, BRIGHT_BLUE, BRIGHT_MAGENTA, BRIGHT_CYAN, BRIGHT_WHITE,
+		BRIGHT_BLACK_BG, BRIGHT_RED_BG, BRIGHT_GREEN_BG, BRIGHT_YELLOW_BG, BRIGHT_BLUE_BG, BRIGHT_MAGENTA_BG, BRIGHT_CYAN_BG, BRIGHT_WHITE_BG,
+	];
+	// Every code must have a name, checked at compile time
+	const _: () = {
+		let mut i = 0;
+		while i < ALL_CODES.len() {
+			assert!(ALL_CODES[i].name().is_some());
+			i += 1;
+		}
+	};
+	&ALL_CODES
+}
+
+/// Iterates over every code constant in [`codes`] with its name.
+///
+/// Pairs [`all_codes`] with [`Code::name`], eg. to present a picker.
+///
+/// ```
+/// let (name, code) = ansi_gfx::iter_named().find(|&(name, _)| name == "RED").unwrap();
+/// assert_eq!(format!("{}{}", code, name), "\x1b[31mRED");
+/// ```
+#[inline]
+pub fn iter_named() -> NamedCodes {
+	NamedCodes { codes: all_codes().iter() }
+}
+
+/// Iterator returned by [`iter_named`].
+#[derive(Clone, Debug)]
+pub struct NamedCodes {
+	codes: slice::Iter<'static, Code>,
+}
+
+impl Iterator for NamedCodes {
+	type Item = (&'static str, Code);
+	fn next(&mut self) -> Option<(&'static str, Code)> {
+		// Every code in the table has a name, checked by all_codes
+		let code = *self.codes.next()?;
+		Some((code.name()?, code))
+	}
+	#[inline]
+	fn size_hint(&self) -> (usize, Option<usize>) {
+		self.codes.size_hint()
+	}
+}
+
+impl ExactSizeIterator for NamedCodes {}
+
+pub mod palette;
+
+pub mod private_modes;
+
+pub mod cursor;
+
+mod state;
+
+mod text;
+pub use self::text::*;
+
+mod parse;
+pub use self::parse::*;
+
+mod color;
+pub use self::color::*;
+
+mod underline;
+pub use self::underline::*;
+
+mod ring;
+pub use self::ring::*;
+
+mod cond;
+pub use self::cond::*;
+
+mod choice;
+pub use self::choice::*;
+
+#[cfg(feature = "alloc")]
+mod buf;
+#[cfg(feature = "alloc")]
+pub use self::buf::*;
+
+#[cfg(feature = "termcolor")]
+mod termcolor_impl;
+
+#[cfg(feature = "crossterm")]
+mod crossterm_impl;
+#[cfg(feature = "crossterm")]
+pub use self::crossterm_impl::*;
+
+#[cfg(feature = "heapless")]
+mod heapless_impl;
+#[cfg(feature = "heapless")]
+pub use self::heapless_impl::*;
+
+impl Code {
+	/// Returns true if the code changes the foreground color.
+	///
+	/// This includes the extended foreground color introducer `38`.
+	pub const fn is_foreground(self) -> bool {
+		matches!(self.__byte, 30..=39 | 90..=97)
+	}
+	/// Returns true if the code changes the background color.
+	///
+	/// This includes the extended background color introducer `48`.
+	pub const fn is_background(self) -> bool {
+		matches!(self.__byte, 40..=49 | 100..=107)
+	}
+	/// Returns true if the code sets or resets an attribute (e.g. bold, underline).
+	///
+	/// The full [`RESET`] is neither an attribute nor a color.
+	pub const fn is_attribute(self) -> bool {
+		matches!(self.__byte, 1..=9 | 21..=29)
+	}
+	/// Returns the code which turns this attribute or color off.
+	///
+	/// Colors are reset to the default foreground or background color.
+	/// Returns `None` for codes without a specific reset, such as the resets themselves.
+	///
+	/// ```
+	/// assert_eq!(ansi_gfx::BOLD.reset_for(), Some(ansi_gfx::RESET_WEIGHT));
+	/// assert_eq!(ansi_gfx::RED_BG.reset_for(), Some(ansi_gfx::DEFAULT_BG));
+	/// assert_eq!(ansi_gfx::RESET_WEIGHT.reset_for(), None);
+	/// ```
+	pub const fn reset_for(self) -> Option<Code> {
+		let byte = match self.__byte {
+			1 | 2 => 22,
+			3 => 23,
+			4 => 24,
+			5 | 6 => 25,
+			7 => 27,
+			8 => 28,
+			9 => 29,
+			30..=38 | 90..=97 => 39,
+			40..=48 | 100..=107 => 49,
+			_ => return None,
+		};
+		Some(Code { __byte: byte })
+	}
+	/// Returns the code which turns this attribute or color off, falling back to the full [`RESET`].
+	///
+	/// Unlike [`Code::reset_for`] this always returns a usable code.
+	///
+	/// ```
+	/// assert_eq!(ansi_gfx::BOLD.reset(), ansi_gfx::RESET_WEIGHT);
+	/// assert_eq!(ansi_gfx::RESET_WEIGHT.reset(), ansi_gfx::RESET);
+	/// ```
+	pub const fn reset(self) -> Code {
+		match self.reset_for() {
+			Some(code) => code,
+			None => codes::RESET,
+		}
+	}
+
+	/// Returns the bright variant of a basic foreground or background color.
+	///
+	/// Returns `None` for anything else, including the bright colors themselves and the attributes.
+	///
+	/// ```
+	/// assert_eq!(ansi_gfx::GREEN.brighten(), Some(ansi_gfx::BRIGHT_GREEN));
+	/// assert_eq!(ansi_gfx::BLUE_BG.brighten(), Some(ansi_gfx::BRIGHT_BLUE_BG));
+	/// assert_eq!(ansi_gfx::BOLD.brighten(), None);
+	/// ```
+	pub const fn brighten(self) -> Option<Code> {
+		match self.__byte {
+			30..=37 | 40..=47 => Some(Code { __byte: self.__byte + 60 }),
+			_ => None,
+		}
+	}
+
+	/// Formats the escape sequence in caret notation, eg. `^[[1m`.
+	///
+	/// This is how `cat -v` and many terminals show the escape character, it reads better than `\x1b` in some logs.
+	///
+	/// ```
+	/// assert_eq!(format!("{}", ansi_gfx::BOLD.debug_caret()), "^[[1m");
+	/// ```
+	#[inline]
+	pub fn debug_caret(&self) -> Caret<'_> {
+		Caret { codes: slice::from_ref(&self.__byte) }
+	}
+
+	/// Returns the escape sequence of the code, eg. `"\x1b[1m"` for [`BOLD`].
+	///
+	/// The sequences of all codes are computed at compile time, this is a table lookup.
+	///
+	/// ```
+	/// const RED: &str = ansi_gfx::RED.as_str();
+	/// assert_eq!(RED, "\x1b[31m");
+	/// ```
+	#[inline]
+	pub const fn as_str(self) -> &'static str {
+		let sequences: &'static [([u8; 6], u8); 256] = &SEQUENCES;
+		let (bytes, len) = &sequences[self.__byte as usize];
+		// The table only contains ASCII
+		unsafe { str::from_utf8_unchecked(bytes.split_at(*len as usize).0) }
+	}
+
+	/// Returns the name of the code's constant, eg. `"BOLD"` for [`BOLD`].
+	///
+	/// Returns `None` for codes without a constant.
+	///
+	/// ```
+	/// assert_eq!(ansi_gfx::BRIGHT_RED_BG.name(), Some("BRIGHT_RED_BG"));
+	/// ```
+	pub const fn name(self) -> Option<&'static str> {
+		let name = match self.__byte {
+			1 => "BOLD",
+			2 => "DIM",
+			3 => "ITALIC",
+			4 => "UNDERLINE",
+			5 => "BLINK",
+			7 => "INVERSE",
+			8 => "HIDDEN",
+			9 => "STRIKE",
+			0 => "RESET",
+			22 => "RESET_WEIGHT",
+			23 => "RESET_ITALIC",
+			24 => "RESET_UNDERLINE",
+			25 => "RESET_BLINK",
+			27 => "RESET_INVERSE",
+			28 => "RESET_HIDDEN",
+			29 => "RESET_STRIKE",
+			30 => "BLACK",
+			31 => "RED",
+			32 => "GREEN",
+			33 => "YELLOW",
+			34 => "BLUE",
+			35 => "MAGENTA",
+			36 => "CYAN",
+			37 => "WHITE",
+			39 => "DEFAULT",
+			40 => "BLACK_BG",
+			41 => "RED_BG",
+			42 => "GREEN_BG",
+			43 => "YELLOW_BG",
+			44 => "BLUE_BG",
+			45 => "MAGENTA_BG",
+			46 => "CYAN_BG",
+			47 => "WHITE_BG",
+			49 => "DEFAULT_BG",
+			90 => "BRIGHT_BLACK",
+			91 => "BRIGHT_RED",
+			92 => "BRIGHT_GREEN",
+			93 => "BRIGHT_YELLOW",
+			94 => "BRIGHT_BLUE",
+			95 => "BRIGHT_MAGENTA",
+			96 => "BRIGHT_CYAN",
+			97 => "BRIGHT_WHITE",
+			100 => "BRIGHT_BLACK_BG",
+			101 => "BRIGHT_RED_BG",
+			102 => "BRIGHT_GREEN_BG",
+			103 => "BRIGHT_YELLOW_BG",
+			104 => "BRIGHT_BLUE_BG",
+			105 => "BRIGHT_MAGENTA_BG",
+			106 => "BRIGHT_CYAN_BG",
+			107 => "BRIGHT_WHITE_BG",
+			_ => return None,
+		};
+		Some(name)
+	}
+}
+
+/// Returns the terminfo capability name for the code.
+///
+/// The name can be passed to `tput` to produce the equivalent escape sequence for the current terminal.
+/// Returns `None` for codes without a direct parameterless capability, such as the colors.
+///
+/// Note that `smxx` and `rmxx` (strikethrough) are extended capabilities not every terminfo database defines.
+///
+/// ```
+/// assert_eq!(ansi_gfx::terminfo_name(ansi_gfx::UNDERLINE), Some("smul"));
+/// assert_eq!(ansi_gfx::terminfo_name(ansi_gfx::RED), None);
+/// ```
+pub const fn terminfo_name(code: Code) -> Option<&'static str> {
+	let name = match code.__byte {
+		0 => "sgr0",
+		1 => "bold",
+		2 => "dim",
+		3 => "sitm",
+		4 => "smul",
+		5 => "blink",
+		7 => "rev",
+		8 => "invis",
+		9 => "smxx",
+		23 => "ritm",
+		24 => "rmul",
+		29 => "rmxx",
+		_ => return None,
+	};
+	Some(name)
+}
+
+/// Iterates over the logical segments of a code list.
+///
+/// Extended colors (`38;5;n` and `38;2;r;g;b`) are yielded as a single segment.
+/// A malformed extended color (eg. `38;1`) yields all remaining codes as a single segment,
+/// terminals may consume any number of the following codes so transforms must not split, move or drop codes out of it.
+#[derive(Clone)]
+struct Segments<'a> {
+	codes: &'a [u8],
+}
+
+impl<'a> Iterator for Segments<'a> {
+	type Item = &'a [u8];
+	fn next(&mut self) -> Option<&'a [u8]> {
+		let len = match self.codes {
+			[] => return None,
+			[38 | 48, 5, ..] => 3,
+			[38 | 48, 2, ..] => 5,
+			[38 | 48, _, ..] => self.codes.len(),
+			_ => 1,
+		};
+		let (head, tail) = self.codes.split_at(len.min(self.codes.len()));
+		self.codes = tail;
+		Some(head)
+	}
+}
+
+#[inline]
+fn segments(codes: &[u8]) -> Segments<'_> {
+	Segments { codes }
+}
+
+fn sort_codes(codes: &mut [u8]) {
+	fn category(code: u8) -> u8 {
+		let code = Code { __byte: code };
+		if code.is_foreground() { 1 } else if code.is_background() { 2 } else { 0 }
+	}
+	fn segment_len(codes: &[u8]) -> usize {
+		segments(codes).next().map_or(0, <[u8]>::len)
+	}
+	let mut run = 0;
+	let mut i = 0;
+	while i < codes.len() {
+		let len = segment_len(&codes[i..]);
+		// Truncated extended colors would absorb the codes after them if moved
+		let truncated = match codes[i..i + len] {
+			[38 | 48, 5, _] | [38 | 48, 2, _, _, _] => false,
+			[38 | 48, ..] => true,
+			_ => false,
+		};
+		if truncated {
+			break;
+		}
+		if codes[i..i + len] == [0] {
+			run = i + len;
+			i += len;
+			continue;
+		}
+		let cat = category(codes[i]);
+		let mut pos = run;
+		while pos < i && category(codes[pos]) <= cat {
+			pos += segment_len(&codes[pos..]);
+		}
+		codes[pos..i + len].rotate_right(len);
+		i += len;
+	}
+}
+
+/// Formats the code list without its color codes.
+///
+/// Foreground, background and extended colors are removed, everything else is preserved.
+/// Returns an empty string if no codes remain, or `None` if the buffer is too small.
+///
+/// ```
+/// let mut buf = [0u8; 16];
+/// assert_eq!(ansi_gfx::strip_colors(&[1, 31], &mut buf), Some("\x1b[1m"));
+/// ```
+pub fn strip_colors<'a>(codes: &[u8], out: &'a mut [u8]) -> Option<&'a str> {
+	display_filter(codes, out, |code| !code.is_foreground() && !code.is_background())
+}
+
+/// Formats the code list without its attribute codes.
+///
+/// Attributes and their resets are removed, everything else is preserved.
+/// Returns an empty string if no codes remain, or `None` if the buffer is too small.
+pub fn strip_attributes<'a>(codes: &[u8], out: &'a mut [u8]) -> Option<&'a str> {
+	display_filter(codes, out, |code| !code.is_attribute())
+}
+
+/// Formats the code list keeping only the codes pagers reliably render.
+///
+/// Pagers such as `less -R` interpret a limited subset of SGR codes. The whitelist is:
+///
+/// * The full [`RESET`].
+/// * [`BOLD`], [`DIM`], [`ITALIC`], [`UNDERLINE`], [`BLINK`] and [`INVERSE`] and their resets.
+/// * All foreground and background colors, including the extended palette and RGB colors.
+///
+/// Everything else (eg. rapid blink, hidden, strikethrough, framed or overline) is dropped.
+/// Returns an empty string if no codes remain, or `None` if the buffer is too small.
+///
+/// ```
+/// let mut buf = [0u8; 16];
+/// assert_eq!(ansi_gfx::pager_safe(&[1, 53, 31], &mut buf), Some("\x1b[1;31m"));
+/// ```
+pub fn pager_safe<'a>(codes: &[u8], out: &'a mut [u8]) -> Option<&'a str> {
+	display_filter(codes, out, |code| {
+		code.is_foreground() || code.is_background() || matches!(code.__byte, 0..=5 | 7 | 22..=25 | 27)
+	})
+}
+
+/// Escape sequences of every code and their lengths.
+const SEQUENCES: [([u8; 6], u8); 256] = {
+	let mut table = [([0u8; 6], 0u8); 256];
+	let mut i = 0;
+	while i < 256 {
+		let code = i as u8;
+		let mut bytes = [0x1b, b'[', 0, 0, 0, 0];
+		let mut len = 2;
+		if code >= 100 {
+			bytes[len] = b'0' + code / 100;
+			len += 1;
+		}
+		if code >= 10 {
+			bytes[len] = b'0' + code / 10 % 10;
+			len += 1;
+		}
+		bytes[len] = b'0' + code % 10;
+		bytes[len + 1] = b'm';
+		table[i] = (bytes, len as u8 + 2);
+		i += 1;
+	}
+	table
+};
+
 impl fmt::Display for Code {
+	#[inline]
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-		let mut buf = [0u8; 8];
-		f.write_str(display(slice::from_ref(&self.__byte), &mut buf).ok_or(fmt::Error)?)?;
-		Ok(())
+		// Fast path writing the precomputed escape sequence
+		f.write_str(self.as_str())
 	}
 }
 
+/// Formats the code as a string literal.
+///
+/// The default form shows the escape character as the text `\x1b` followed by the readable parameters, eg. `"\x1b[1m"`.
+/// The alternate form `{:#?}` shows every byte of the escape sequence as a hex escape, eg. `"\x1b\x5b\x31\x6d"`.
+///
+/// Both forms can be pasted into Rust source as a string literal producing the actual escape sequence.
 impl fmt::Debug for Code {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-		write!(f, "\"\\x1b[{}m\"", self.__byte)
+		debug(slice::from_ref(&self.__byte), f)
 	}
 }
 
 /// Format graphics codes as an ANSI escape sequence.
 ///
 /// Create an instance using the [`mode!`] macro.
+///
+/// Styles do nothing unless formatted, forgetting to use the result is warned about:
+///
+/// ```compile_fail
+/// #![deny(unused_must_use)]
+/// fn main() {
+/// 	ansi_gfx::mode!(BOLD; RED);
+/// }
+/// ```
+#[derive(Copy, Clone)]
+#[must_use]
 pub struct Print<T: AsRef<[u8]>> {
 	#[doc(hidden)]
 	pub __codes: T,
 }
 
 impl<T: AsRef<[u8]>> Print<T> {
+	/// Wraps a code list.
+	///
+	/// The codes are formatted as is, extended colors span several bytes (eg. `38;5;n`).
+	///
+	/// ```
+	/// let style = ansi_gfx::Print::from_codes([1u8, 31]);
+	/// assert_eq!(format!("{}", style), format!("{}", ansi_gfx::mode!(BOLD; RED)));
+	/// ```
+	#[inline]
+	pub const fn from_codes(codes: T) -> Print<T> {
+		Print { __codes: codes }
+	}
+
+	/// Returns the raw codes.
+	///
+	/// Extended colors span several bytes, eg. `38;5;n`.
+	///
+	/// ```
+	/// assert_eq!(ansi_gfx::mode!(BOLD; RED).bytes(), &[1, 31]);
+	/// ```
+	#[inline]
+	pub fn bytes(&self) -> &[u8] {
+		self.__codes.as_ref()
+	}
+
+	/// Iterates over the attribute codes, skipping colors and other codes.
+	///
+	/// Attribute resets such as [`RESET_WEIGHT`] are attributes too, see [`Code::is_attribute`].
+	///
+	/// ```
+	/// use ansi_gfx::{BOLD, UNDERLINE};
+	/// let attrs: Vec<_> = ansi_gfx::mode!(BOLD; RED; UNDERLINE).attributes().collect();
+	/// assert_eq!(attrs, [BOLD, UNDERLINE]);
+	/// ```
+	#[inline]
+	pub fn attributes(&self) -> Attributes<'_> {
+		Attributes { segments: segments(self.__codes.as_ref()) }
+	}
+
+	/// Describes the style for logging.
+	///
+	/// Codes are shown by name (see [`Code::name`]) and extended colors in `mode!` syntax, codes without a name as their number.
+	///
+	/// ```
+	/// let style = ansi_gfx::mode!(BOLD; RED; BG PAL 28);
+	/// assert_eq!(format!("{}", style.describe()), "[BOLD, RED, BG PAL 28]");
+	/// ```
+	#[inline]
+	pub fn describe(&self) -> Describe<'_> {
+		Describe { codes: self.__codes.as_ref() }
+	}
+
 	/// Normalizes the generic type to `&[u8]`.
-	pub fn erase<'a>(&'a self) -> Print<&'a [u8]> {
+	#[must_use = "styles do nothing unless formatted"]
+	pub fn erase(&self) -> Print<&[u8]> {
 		Print { __codes: self.__codes.as_ref() }
 	}
+
+	/// Compares the visual effect of two styles.
+	///
+	/// Unlike comparing the codes directly, duplicate codes and the order of independent codes are ignored.
+	/// When a color is set more than once only the last one counts.
+	///
+	/// ```
+	/// use ansi_gfx::mode;
+	/// assert!(mode!(BOLD; RED).style_eq(&mode!(RED; BOLD; BOLD)));
+	/// assert!(mode!(GREEN; RED).style_eq(&mode!(RED)));
+	/// assert!(!mode!(BOLD; RED).style_eq(&mode!(BOLD; GREEN)));
+	/// ```
+	pub fn style_eq(&self, other: &Print<impl AsRef<[u8]>>) -> bool {
+		state::State::new(self.__codes.as_ref()) == state::State::new(other.__codes.as_ref())
+	}
+
+	/// Returns true if applying this style after the other style changes nothing.
+	///
+	/// Every code of this style must already be in effect in the other style.
+	/// When a color is set more than once only the last one counts.
+	///
+	/// ```
+	/// use ansi_gfx::mode;
+	/// assert!(mode!(BOLD).is_subset_of(&mode!(BOLD; RED)));
+	/// assert!(!mode!(RED).is_subset_of(&mode!(RED; GREEN)));
+	/// ```
+	pub fn is_subset_of(&self, other: &Print<impl AsRef<[u8]>>) -> bool {
+		let base = state::State::new(other.__codes.as_ref());
+		let mut state = base;
+		for segment in segments(self.__codes.as_ref()) {
+			state.apply(segment);
+		}
+		state == base
+	}
+
+	/// Returns true if applying the other style after this style overrides part of this style.
+	///
+	/// The styles conflict if the other style sets a different foreground or background color than this style sets,
+	/// or turns off (or back on) an attribute this style turns on (or off), eg. with a [`RESET`].
+	/// Colors and attributes this style leaves alone are free to change.
+	///
+	/// ```
+	/// use ansi_gfx::mode;
+	/// assert!(mode!(RED).conflicts_with(&mode!(GREEN)));
+	/// assert!(!mode!(RED).conflicts_with(&mode!(BOLD; BLUE_BG)));
+	/// ```
+	pub fn conflicts_with(&self, other: &Print<impl AsRef<[u8]>>) -> bool {
+		let base = state::State::new(self.__codes.as_ref());
+		let mut state = base;
+		for segment in segments(other.__codes.as_ref()) {
+			state.apply(segment);
+		}
+		base.fg.is_some() && state.fg != base.fg
+			|| base.bg.is_some() && state.bg != base.bg
+			|| base.on & !state.on != 0
+			|| base.off & !state.off != 0
+			|| base.other.iter().zip(&state.other).any(|(&base, &state)| base & !state != 0)
+	}
+
+	/// Sorts the codes in canonical order.
+	///
+	/// The order is attributes (and any other codes) first, then foreground colors, then background colors.
+	/// The sort is stable and extended colors are kept intact.
+	/// A [`RESET`] cancels everything before it, so codes are never moved across a reset.
+	///
+	/// ```
+	/// let style = ansi_gfx::mode!(BLUE_BG; FG PAL 9; BOLD; UNDERLINE).sorted();
+	/// assert_eq!(format!("{}", style), format!("{}", ansi_gfx::mode!(BOLD; UNDERLINE; FG PAL 9; BLUE_BG)));
+	/// ```
+	pub fn sorted(mut self) -> Print<T> where T: AsMut<[u8]> {
+		sort_codes(self.__codes.as_mut());
+		self
+	}
+
+	/// Formats the style starting from a clean state.
+	///
+	/// Emits a [`RESET`] followed by the codes in a single escape sequence, without allocating.
+	/// This prevents styles from bleeding in at the cost of two extra bytes per escape sequence.
+	/// Use `mode!(RESET; ...)` for the same effect at compile time.
+	///
+	/// ```
+	/// let style = ansi_gfx::mode!(BOLD; RED);
+	/// assert_eq!(format!("{}", style.fresh()), "\x1b[0;1;31m");
+	/// ```
+	#[inline]
+	pub fn fresh(&self) -> Fresh<'_> {
+		Fresh { codes: self.__codes.as_ref() }
+	}
+
+	/// Compares the codes of two styles ignoring the [`RESET`] codes.
+	///
+	/// ```
+	/// use ansi_gfx::Print;
+	/// assert!(Print::from_codes([31u8, 0]).eq_ignoring_resets(&Print::from_codes([31u8])));
+	/// assert!(!Print::from_codes([31u8]).eq_ignoring_resets(&Print::from_codes([1u8, 31])));
+	/// ```
+	pub fn eq_ignoring_resets(&self, other: &Print<impl AsRef<[u8]>>) -> bool {
+		let not_reset = |segment: &&[u8]| *segment != [0];
+		segments(self.__codes.as_ref()).filter(not_reset).eq(segments(other.__codes.as_ref()).filter(not_reset))
+	}
+
+	/// Formats the escape sequence in caret notation, eg. `^[[1;31m`.
+	///
+	/// See [`Code::debug_caret`].
+	#[inline]
+	pub fn debug_caret(&self) -> Caret<'_> {
+		Caret { codes: self.__codes.as_ref() }
+	}
+
+	/// Returns true if the style starts with a [`RESET`].
+	///
+	/// ```
+	/// assert!(ansi_gfx::mode!(RESET; BOLD).starts_reset());
+	/// assert!(!ansi_gfx::mode!(BOLD; RESET).starts_reset());
+	/// ```
+	pub fn starts_reset(&self) -> bool {
+		segments(self.__codes.as_ref()).next() == Some(&[0])
+	}
+
+	/// Returns true if the style ends with a [`RESET`].
+	///
+	/// Extended color parameters are not mistaken for a reset.
+	///
+	/// ```
+	/// assert!(ansi_gfx::mode!(BOLD; RESET).ends_reset());
+	/// assert!(!ansi_gfx::mode!(FG PAL 0).ends_reset());
+	/// ```
+	pub fn ends_reset(&self) -> bool {
+		segments(self.__codes.as_ref()).last() == Some(&[0])
+	}
+
+	/// Returns the minimal style which turns off what this style turns on.
+	///
+	/// Every attribute and color is reset with its specific code (see [`Code::reset_for`]) instead of a blanket [`RESET`], preserving the surrounding style.
+	/// Falls back to [`RESET`] if the style contains codes without a specific reset.
+	///
+	/// ```
+	/// let style = ansi_gfx::mode!(BOLD; RED);
+	/// assert_eq!(format!("{}hi{}", style, style.scoped_reset()), "\x1b[1;31mhi\x1b[22;39m");
+	/// ```
+	pub fn scoped_reset(&self) -> Print<ResetCodes> {
+		// Bit `n` for reset code `22 + n`
+		let mut mask = 0u32;
+		for segment in segments(self.__codes.as_ref()) {
+			let code = Code { __byte: segment[0] };
+			match code.reset_for() {
+				Some(reset) => mask |= 1 << (reset.__byte - 22),
+				// Resets themselves turn nothing on
+				None if matches!(code.__byte, 0 | 22..=25 | 27..=29 | 39 | 49) => (),
+				None => return Print { __codes: ResetCodes { bytes: [0; 9], len: 1 } },
+			}
+		}
+		let mut codes = ResetCodes { bytes: [0; 9], len: 0 };
+		for n in 0..28 {
+			if mask & 1 << n != 0 {
+				codes.bytes[codes.len as usize] = 22 + n;
+				codes.len += 1;
+			}
+		}
+		Print { __codes: codes }
+	}
 }
 
-impl<T: AsRef<[u8]>> fmt::Display for Print<T> {
+impl<T: AsRef<[u8]>> From<T> for Print<T> {
+	#[inline]
+	fn from(codes: T) -> Print<T> {
+		Print { __codes: codes }
+	}
+}
+
+/// Display adapter returned by [`Print::describe`].
+#[derive(Copy, Clone, Debug)]
+#[must_use]
+pub struct Describe<'a> {
+	codes: &'a [u8],
+}
+
+impl<'a> fmt::Display for Describe<'a> {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-		let codes = self.__codes.as_ref();
-		if codes.len() > 0 {
-			let mut buf = [0u8; 64];
-			f.write_str(display(codes, &mut buf).ok_or(fmt::Error)?)?;
+		f.write_str("[")?;
+		for (i, segment) in segments(self.codes).enumerate() {
+			if i > 0 {
+				f.write_str(", ")?;
+			}
+			let ground = if segment[0] == 38 { "FG" } else { "BG" };
+			match *segment {
+				[38 | 48, 5, index] => write!(f, "{} PAL {}", ground, index)?,
+				[38 | 48, 2, red, green, blue] => write!(f, "{} RGB {}, {}, {}", ground, red, green, blue)?,
+				[code] => match (Code { __byte: code }).name() {
+					Some(name) => f.write_str(name)?,
+					None => write!(f, "{}", code)?,
+				},
+				_ => {
+					for (j, code) in segment.iter().enumerate() {
+						write!(f, "{}{}", if j > 0 { ";" } else { "" }, code)?;
+					}
+				},
+			}
+		}
+		f.write_str("]")
+	}
+}
+
+/// Display adapter returned by [`Code::debug_caret`] and [`Print::debug_caret`].
+#[derive(Copy, Clone, Debug)]
+#[must_use]
+pub struct Caret<'a> {
+	codes: &'a [u8],
+}
+
+impl<'a> fmt::Display for Caret<'a> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		if !self.codes.is_empty() {
+			f.write_str("^[[")?;
+			write_params(self.codes, b'm', f)?;
 		}
 		Ok(())
 	}
 }
 
+/// Iterator returned by [`Print::attributes`].
+#[derive(Clone)]
+pub struct Attributes<'a> {
+	segments: Segments<'a>,
+}
+
+impl<'a> Iterator for Attributes<'a> {
+	type Item = Code;
+	fn next(&mut self) -> Option<Code> {
+		self.segments.by_ref()
+			.map(|segment| Code { __byte: segment[0] })
+			.find(|code| code.is_attribute())
+	}
+}
+
+/// Codes returned by [`Print::scoped_reset`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct ResetCodes {
+	bytes: [u8; 9],
+	len: u8,
+}
+
+impl AsRef<[u8]> for ResetCodes {
+	#[inline]
+	fn as_ref(&self) -> &[u8] {
+		&self.bytes[..self.len as usize]
+	}
+}
+
+/// Codes returned by [`Print::with_reset`].
+///
+/// Holds up to [`MAX_SGR_PARAMS`] codes inline, enough for any [`mode!`] followed by a reset.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct ResetAppended {
+	bytes: [u8; MAX_SGR_PARAMS],
+	len: u16,
+}
+
+impl AsRef<[u8]> for ResetAppended {
+	#[inline]
+	fn as_ref(&self) -> &[u8] {
+		&self.bytes[..self.len as usize]
+	}
+}
+
+impl<const M: usize> Print<[u8; M]> {
+	/// Number of codes in the style.
+	///
+	/// Together with [`MAX_LEN`](Self::MAX_LEN) this sizes buffers at compile time, eg. to parse the style back:
+	///
+	/// ```
+	/// ansi_gfx::style!(BOLD_RED = BOLD; RED);
+	/// let mut buf = [0u8; ansi_gfx::Print::<[u8; 2]>::CODE_COUNT];
+	/// assert!(ansi_gfx::parse(&BOLD_RED.to_string(), &mut buf).is_some());
+	/// ```
+	pub const CODE_COUNT: usize = M;
+
+	/// Worst-case length of the formatted escape sequence.
+	pub const MAX_LEN: usize = if M == 0 { 0 } else { 2 + 4 * M };
+
+	/// Returns the style followed by a [`RESET`].
+	///
+	/// The codes and a trailing `0` are formatted in a single escape sequence, without allocating.
+	/// The style is immediately undone, the sequence cannot bleed into what follows.
+	/// Use `mode!(...; RESET)` for the same effect at compile time.
+	///
+	/// The style must have fewer than [`MAX_SGR_PARAMS`] codes, this is checked at compile time.
+	///
+	/// ```
+	/// let style = ansi_gfx::mode!(BOLD; RED).with_reset();
+	/// assert_eq!(style.bytes(), [1, 31, 0]);
+	/// assert_eq!(format!("{}", style), "\x1b[1;31;0m");
+	/// ```
+	pub const fn with_reset(self) -> Print<ResetAppended> {
+		const { assert!(M < MAX_SGR_PARAMS, "too many codes for with_reset") };
+		let mut bytes = [0u8; MAX_SGR_PARAMS];
+		let mut i = 0;
+		while i < M {
+			bytes[i] = self.__codes[i];
+			i += 1;
+		}
+		// The trailing byte is already the RESET code
+		Print { __codes: ResetAppended { bytes, len: M as u16 + 1 } }
+	}
+
+	/// Formats the escape sequence into an inline string.
+	///
+	/// The capacity `N` must be at least [`MAX_LEN`](Self::MAX_LEN), this is checked at compile time.
+	///
+	/// ```
+	/// let s: ansi_gfx::InlineStr<16> = ansi_gfx::mode!(BOLD; RED).to_inline();
+	/// assert_eq!(&*s, "\x1b[1;31m");
+	/// ```
+	///
+	/// ```compile_fail
+	/// let s: ansi_gfx::InlineStr<8> = ansi_gfx::mode!(BOLD; RED).to_inline();
+	/// ```
+	pub fn to_inline<const N: usize>(&self) -> InlineStr<N> {
+		const { assert!(N >= Self::MAX_LEN, "InlineStr capacity is too small") };
+		let mut buf = [0u8; N];
+		let mut writer = BufWriter::new(&mut buf);
+		// Cannot fail as the capacity is large enough
+		let _ = write_codes(&self.__codes, &mut writer);
+		let len = writer.len;
+		InlineStr { buf, len }
+	}
+}
+
+/// Display adapter returned by [`Print::fresh`].
+#[derive(Copy, Clone, Debug)]
+#[must_use]
+pub struct Fresh<'a> {
+	codes: &'a [u8],
+}
+
+impl<'a> fmt::Display for Fresh<'a> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		if self.codes.is_empty() {
+			f.write_str("\x1b[0m")
+		}
+		else {
+			f.write_str("\x1b[0;")?;
+			write_params(self.codes, b'm', f)
+		}
+	}
+}
+
+impl<T: AsRef<[u8]>> fmt::Display for Print<T> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write_codes(self.__codes.as_ref(), f)
+	}
+}
+
+/// Formats the codes as a string literal.
+///
+/// See [`Code`]'s `Debug` impl for the default and alternate forms.
 impl<T: AsRef<[u8]>> fmt::Debug for Print<T> {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
 		debug(self.__codes.as_ref(), f)
@@ -348,7 +1325,14 @@ impl<T: AsRef<[u8]>> fmt::Debug for Print<T> {
 
 #[inline(never)]
 fn debug(codes: &[u8], f: &mut fmt::Formatter) -> fmt::Result {
-	if codes.len() > 0 {
+	if f.alternate() {
+		if !codes.is_empty() {
+			f.write_str("\"")?;
+			write_codes(codes, &mut HexEscape(f))?;
+			f.write_str("\"")?;
+		}
+	}
+	else if !codes.is_empty() {
 		write!(f, "\"\\x1b[")?;
 		for i in 0..codes.len() {
 			let suffix = if i + 1 == codes.len() { 'm' } else { ';' };
@@ -359,8 +1343,24 @@ fn debug(codes: &[u8], f: &mut fmt::Formatter) -> fmt::Result {
 	Ok(())
 }
 
+/// Writes every byte as a `\xNN` escape.
+struct HexEscape<'a, 'b>(&'a mut fmt::Formatter<'b>);
+
+impl<'a, 'b> fmt::Write for HexEscape<'a, 'b> {
+	fn write_str(&mut self, s: &str) -> fmt::Result {
+		for byte in s.bytes() {
+			write!(self.0, "\\x{:02x}", byte)?;
+		}
+		Ok(())
+	}
+}
+
+/// Writes the decimal code followed by the suffix and returns the number of bytes written.
+///
+/// The buffer must hold at least 4 bytes: up to 3 digits for a `u8` plus the suffix.
+/// Smaller buffers are rejected by returning 0 even if the code would fit, callers rely on this to detect overflow.
 #[inline]
-fn display_code(mut code: u8, suffix: u8, buf: &mut [u8]) -> usize {
+fn display_code(code: u8, suffix: u8, buf: &mut [u8]) -> usize {
 	if buf.len() < 4 {
 		return 0;
 	}
@@ -368,42 +1368,310 @@ fn display_code(mut code: u8, suffix: u8, buf: &mut [u8]) -> usize {
 	let mut i = 0;
 	if code >= 100 {
 		buf[i] = b'0' + code / 100;
-		code = code % 100;
 		i += 1;
 	}
 	if code >= 10 {
-		buf[i] = b'0' + code / 10;
-		code = code % 10;
+		buf[i] = b'0' + code / 10 % 10;
 		i += 1;
 	}
-	buf[i] = b'0' + code;
+	buf[i] = b'0' + code % 10;
 	i += 1;
 	buf[i] = suffix;
 	i += 1;
-	return i;
+	debug_assert!(i <= 4);
+	i
 }
 
+/// Writes graphics codes as an ANSI escape sequence.
+///
+/// The codes are serialized directly into the writer, there is no intermediate buffer and no length limit.
+/// Writes nothing if the code list is empty.
+///
+/// ```
+/// let mut s = String::new();
+/// ansi_gfx::write_codes(&[1, 31], &mut s).unwrap();
+/// assert_eq!(s, "\x1b[1;31m");
+/// ```
 #[inline(never)]
-fn display<'a>(codes: &[u8], buf: &'a mut [u8]) -> Option<&'a str> {
+pub fn write_codes<W: fmt::Write + ?Sized>(codes: &[u8], w: &mut W) -> fmt::Result {
+	if !codes.is_empty() {
+		w.write_str("\x1b[")?;
+		write_params(codes, b'm', w)?;
+	}
+	Ok(())
+}
+
+/// Writes the numeric parameters separated by `;` followed by the terminator.
+///
+/// Shared by the SGR codes and the other control sequences, eg. cursor positions and private modes which don't fit a `u8`.
+fn write_params<P: Copy + Into<u16>, W: fmt::Write + ?Sized>(params: &[P], terminator: u8, w: &mut W) -> fmt::Result {
+	for i in 0..params.len() {
+		let suffix = if i + 1 == params.len() { terminator } else { b';' };
+		// Up to 5 digits for a `u16` plus the suffix
+		let mut buf = [0u8; 6];
+		let mut start = buf.len() - 1;
+		buf[start] = suffix;
+		let mut value: u16 = params[i].into();
+		loop {
+			start -= 1;
+			buf[start] = b'0' + (value % 10) as u8;
+			value /= 10;
+			if value == 0 {
+				break;
+			}
+		}
+		w.write_str(unsafe { str::from_utf8_unchecked(&buf[start..]) })?;
+	}
+	Ok(())
+}
+
+/// Formats the codes as bare SGR parameters.
+///
+/// Produces the parameters separated by `;` without the `ESC [` introducer or the `m` terminator, eg. to assemble a CSI by hand.
+/// Returns `None` if the buffer is too small.
+///
+/// ```
+/// let mut buf = [0u8; 16];
+/// assert_eq!(ansi_gfx::params_only(&[1, 31], &mut buf), Some("1;31"));
+/// ```
+pub fn params_only<'a>(codes: &[u8], out: &'a mut [u8]) -> Option<&'a str> {
+	let mut writer = BufWriter::new(out);
+	for i in 0..codes.len() {
+		let mut buf = [0u8; 4];
+		let len = display_code(codes[i], b';', &mut buf);
+		let len = if i + 1 == codes.len() { len - 1 } else { len };
+		fmt::Write::write_str(&mut writer, unsafe { str::from_utf8_unchecked(&buf[..len]) }).ok()?;
+	}
+	Some(writer.into_str())
+}
+
+/// Formats a control sequence with numeric parameters and a terminator.
+///
+/// The parameters are separated by `;` the same way as graphics codes, eg. `b'A'` for cursor up or `b'J'` for erase in display.
+/// Without parameters only the terminator follows the introducer, terminals then use the default parameters.
+/// Returns `None` if the terminator is not ASCII or the buffer is too small.
+///
+/// ```
+/// let mut buf = [0u8; 16];
+/// assert_eq!(ansi_gfx::display_csi(&[3], b'A', &mut buf), Some("\x1b[3A"));
+/// assert_eq!(ansi_gfx::display_csi(&[1, 31], b'm', &mut buf), Some("\x1b[1;31m"));
+/// ```
+pub fn display_csi<'a>(params: &[u8], terminator: u8, out: &'a mut [u8]) -> Option<&'a str> {
+	if !terminator.is_ascii() {
+		return None;
+	}
+	let mut writer = BufWriter::new(out);
+	fmt::Write::write_str(&mut writer, "\x1b[").ok()?;
+	if params.is_empty() {
+		let terminator = [terminator];
+		fmt::Write::write_str(&mut writer, str::from_utf8(&terminator).ok()?).ok()?;
+	}
+	else {
+		write_params(params, terminator, &mut writer).ok()?;
+	}
+	Some(writer.into_str())
+}
+
+/// Formats the code list without redundant resets.
+///
+/// A [`RESET`] nullifies every code before it, only the codes from the last reset onward are kept.
+/// Returns `None` if the buffer is too small.
+///
+/// ```
+/// let mut buf = [0u8; 16];
+/// assert_eq!(ansi_gfx::dedup_resets(&[1, 0, 0, 31], &mut buf), Some("\x1b[0;31m"));
+/// ```
+pub fn dedup_resets<'a>(codes: &[u8], out: &'a mut [u8]) -> Option<&'a str> {
+	let mut start = 0;
+	let mut pos = 0;
+	for segment in segments(codes) {
+		if segment == [0] {
+			start = pos;
+		}
+		pos += segment.len();
+	}
+	let mut writer = BufWriter::new(out);
+	write_codes(&codes[start..], &mut writer).ok()?;
+	Some(writer.into_str())
+}
+
+/// Formats every logical segment as its own escape sequence.
+///
+/// Some terminals mishandle long combined parameter lists, emitting one escape sequence per code avoids this at the cost of extra bytes.
+/// Extended colors are kept together in a single escape sequence.
+/// Returns `None` if the buffer is too small.
+///
+/// ```
+/// let mut buf = [0u8; 32];
+/// let s = ansi_gfx::split_sequences(&[1, 38, 5, 28], &mut buf);
+/// assert_eq!(s, Some("\x1b[1m\x1b[38;5;28m"));
+/// ```
+pub fn split_sequences<'a>(codes: &[u8], out: &'a mut [u8]) -> Option<&'a str> {
+	let mut writer = BufWriter::new(out);
+	for segment in segments(codes) {
+		write_codes(segment, &mut writer).ok()?;
+	}
+	Some(writer.into_str())
+}
+
+/// Returns the escape sequence which returns to the terminal's default style.
+///
+/// Same as formatting [`RESET`], usable where a string is needed without formatting.
+///
+/// ```
+/// assert_eq!(ansi_gfx::reset_sequence(), "\x1b[0m");
+/// ```
+#[inline]
+pub const fn reset_sequence() -> &'static str {
+	"\x1b[0m"
+}
+
+/// Returns the escape sequence which returns to the terminal's default style including the underline color.
+///
+/// Some terminals keep the underline color (set with `58`) across a [`RESET`], this also resets it with `59`.
+///
+/// ```
+/// assert_eq!(ansi_gfx::reset_sequence_full(), "\x1b[0;59m");
+/// ```
+#[inline]
+pub const fn reset_sequence_full() -> &'static str {
+	"\x1b[0;59m"
+}
+
+/// Formats a reset to emit before terminal graphics protocol commands.
+///
+/// Graphics protocols such as Kitty (`ESC _ G`), iTerm2 (`ESC ] 1337`) and sixel (`ESC P q`) transmit images as string sequences.
+/// SGR escape sequences emitted while such a string sequence is unterminated are swallowed as part of its payload,
+/// and an active style may be applied to the cells the image covers.
+/// Terminals which honor the sixel background select paint the transparent pixels with the current SGR background color,
+/// and an active [`INVERSE`] swaps the colors of the image on some terminals.
+///
+/// This emits a string terminator (`ESC \`), which terminals ignore outside a string sequence, followed by a [`RESET`].
+/// Returns `None` if the buffer is too small.
+///
+/// ```
+/// let mut buf = [0u8; 8];
+/// assert_eq!(ansi_gfx::before_graphics(&mut buf), Some("\x1b\\\x1b[0m"));
+/// ```
+#[doc(alias = "sixel_guard")]
+pub fn before_graphics(out: &mut [u8]) -> Option<&str> {
+	let mut writer = BufWriter::new(out);
+	fmt::Write::write_str(&mut writer, "\x1b\\\x1b[0m").ok()?;
+	Some(writer.into_str())
+}
+
+/// Formats several styles into one buffer.
+///
+/// The escape sequences are concatenated in order.
+/// Returns `None` if the buffer is too small.
+///
+/// ```
+/// use ansi_gfx::mode;
+/// let mut buf = [0u8; 32];
+/// let s = ansi_gfx::render_all(&[mode!(BOLD).erase(), mode!(RED).erase()], &mut buf);
+/// assert_eq!(s, Some("\x1b[1m\x1b[31m"));
+/// ```
+pub fn render_all<'a>(prints: &[Print<&[u8]>], out: &'a mut [u8]) -> Option<&'a str> {
+	let mut writer = BufWriter::new(out);
+	for print in prints {
+		write_codes(print.__codes, &mut writer).ok()?;
+	}
+	Some(writer.into_str())
+}
+
+/// Fixed capacity string stored inline.
+///
+/// Returned by [`Print::to_inline`].
+#[derive(Copy, Clone)]
+pub struct InlineStr<const N: usize> {
+	buf: [u8; N],
+	len: usize,
+}
+
+impl<const N: usize> InlineStr<N> {
+	/// Returns the string slice.
+	#[inline]
+	pub fn as_str(&self) -> &str {
+		unsafe { str::from_utf8_unchecked(&self.buf[..self.len]) }
+	}
+}
+
+impl<const N: usize> ops::Deref for InlineStr<N> {
+	type Target = str;
+	#[inline]
+	fn deref(&self) -> &str {
+		self.as_str()
+	}
+}
+
+impl<const N: usize> AsRef<str> for InlineStr<N> {
+	#[inline]
+	fn as_ref(&self) -> &str {
+		self.as_str()
+	}
+}
+
+impl<const N: usize> fmt::Display for InlineStr<N> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.write_str(self.as_str())
+	}
+}
+
+impl<const N: usize> fmt::Debug for InlineStr<N> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		fmt::Debug::fmt(self.as_str(), f)
+	}
+}
+
+/// Writes into a fixed size buffer, fails if the buffer is too small.
+pub(crate) struct BufWriter<'a> {
+	buf: &'a mut [u8],
+	len: usize,
+}
+
+impl<'a> BufWriter<'a> {
+	#[inline]
+	pub fn new(buf: &'a mut [u8]) -> BufWriter<'a> {
+		BufWriter { buf, len: 0 }
+	}
+	#[inline]
+	pub fn into_str(self) -> &'a str {
+		let buf = &self.buf[..self.len];
+		unsafe { str::from_utf8_unchecked(buf) }
+	}
+}
+
+impl<'a> fmt::Write for BufWriter<'a> {
+	fn write_str(&mut self, s: &str) -> fmt::Result {
+		let dest = self.buf.get_mut(self.len..self.len + s.len()).ok_or(fmt::Error)?;
+		dest.copy_from_slice(s.as_bytes());
+		self.len += s.len();
+		Ok(())
+	}
+}
+
+#[inline(never)]
+fn display_filter<'a>(codes: &[u8], buf: &'a mut [u8], keep: impl Fn(Code) -> bool) -> Option<&'a str> {
 	if buf.len() < 3 {
 		return None;
 	}
 	buf[0] = 0x1b;
 	buf[1] = b'[';
 	let mut total = 2;
-	{
-		let mut buf = &mut buf[2..];
-		for i in 0..codes.len() {
-			let suffix = if i + 1 == codes.len() { b'm' } else { b';' };
-			let skip = display_code(codes[i], suffix, buf);
+	for segment in segments(codes).filter(|segment| keep(Code { __byte: segment[0] })) {
+		for &code in segment {
+			let skip = display_code(code, b';', &mut buf[total..]);
 			if skip == 0 {
 				return None;
 			}
 			total += skip;
-			buf = &mut buf[skip..];
 		}
 	}
-	let buf = &buf.get(..total)?;
+	if total == 2 {
+		return Some("");
+	}
+	buf[total - 1] = b'm';
+	let buf = &buf[..total];
 	unsafe { Some(str::from_utf8_unchecked(buf)) }
 }
 
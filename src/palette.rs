@@ -0,0 +1,234 @@
+/*!
+Helpers for the 256-color palette.
+
+The palette consists of the 16 basic colors (0–15), a 6×6×6 color cube (16–231) and a 24-step grayscale ramp (232–255).
+*/
+
+use super::{Code, BLACK, WHITE};
+
+/// RGB values of the basic colors, following the VGA palette.
+const BASIC: [(u8, u8, u8); 16] = [
+	(0, 0, 0), (170, 0, 0), (0, 170, 0), (170, 85, 0),
+	(0, 0, 170), (170, 0, 170), (0, 170, 170), (170, 170, 170),
+	(85, 85, 85), (255, 85, 85), (85, 255, 85), (255, 255, 85),
+	(85, 85, 255), (255, 85, 255), (85, 255, 255), (255, 255, 255),
+];
+
+/// Names of the basic colors.
+const BASIC_NAMES: [&str; 16] = [
+	"black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+	"bright_black", "bright_red", "bright_green", "bright_yellow", "bright_blue", "bright_magenta", "bright_cyan", "bright_white",
+];
+
+/// Intensity of the color cube coordinates.
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// Returns the palette index of a color in the 6×6×6 color cube.
+///
+/// The coordinates range from 0 to 5, larger values saturate at 5.
+///
+/// ```
+/// assert_eq!(ansi_gfx::palette::cube_color(0, 0, 0), 16);
+/// assert_eq!(ansi_gfx::palette::cube_color(5, 5, 5), 231);
+/// ```
+#[inline]
+pub const fn cube_color(r: u8, g: u8, b: u8) -> u8 {
+	let r = if r > 5 { 5 } else { r };
+	let g = if g > 5 { 5 } else { g };
+	let b = if b > 5 { 5 } else { b };
+	16 + 36 * r + 6 * g + b
+}
+
+/// Returns the color cube coordinates of a palette index.
+///
+/// Returns `None` if the index is not part of the color cube.
+///
+/// ```
+/// assert_eq!(ansi_gfx::palette::cube_coords(231), Some((5, 5, 5)));
+/// assert_eq!(ansi_gfx::palette::cube_coords(15), None);
+/// ```
+#[inline]
+pub const fn cube_coords(index: u8) -> Option<(u8, u8, u8)> {
+	if index < 16 || index > 231 {
+		return None;
+	}
+	let i = index - 16;
+	Some((i / 36, i / 6 % 6, i % 6))
+}
+
+/// Sub-ranges of the 256-color palette.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum PaletteKind {
+	/// Indices 0–15 are the basic colors.
+	///
+	/// These render the same as the named colors (0–7) and their bright variants (8–15) and are typically themed by the terminal.
+	Basic,
+	/// Indices 16–231 are the 6×6×6 color cube, see [`cube_color`].
+	Cube,
+	/// Indices 232–255 are a grayscale ramp from dark to light, excluding pure black and white.
+	Grayscale,
+}
+
+/// Returns which sub-range of the palette the index belongs to.
+///
+/// ```
+/// use ansi_gfx::palette::{palette_kind, PaletteKind};
+/// assert_eq!(palette_kind(9), PaletteKind::Basic);
+/// assert_eq!(palette_kind(28), PaletteKind::Cube);
+/// assert_eq!(palette_kind(240), PaletteKind::Grayscale);
+/// ```
+#[inline]
+pub const fn palette_kind(index: u8) -> PaletteKind {
+	match index {
+		0..=15 => PaletteKind::Basic,
+		16..=231 => PaletteKind::Cube,
+		_ => PaletteKind::Grayscale,
+	}
+}
+
+/// Returns the RGB values of the 16 basic colors.
+///
+/// The values follow the VGA palette, the same as [`palette_to_rgb`] uses.
+/// Terminals theme these colors, copy and override the table to match a specific theme.
+///
+/// ```
+/// let palette = ansi_gfx::palette::ansi16_palette();
+/// assert_eq!(palette[1], (170, 0, 0));
+/// assert_eq!(palette[9], (255, 85, 85));
+/// ```
+#[inline]
+pub const fn ansi16_palette() -> &'static [(u8, u8, u8); 16] {
+	&BASIC
+}
+
+/// Returns the RGB value of a palette index.
+///
+/// The basic colors follow the VGA palette, the actual colors depend on the terminal's theme.
+/// The color cube and grayscale ramp use the conventional xterm values.
+///
+/// ```
+/// assert_eq!(ansi_gfx::palette::palette_to_rgb(196), (255, 0, 0));
+/// assert_eq!(ansi_gfx::palette::palette_to_rgb(232), (8, 8, 8));
+/// ```
+pub const fn palette_to_rgb(index: u8) -> (u8, u8, u8) {
+	match index {
+		0..=15 => BASIC[index as usize],
+		16..=231 => {
+			let i = index - 16;
+			(CUBE_LEVELS[(i / 36) as usize], CUBE_LEVELS[(i / 6 % 6) as usize], CUBE_LEVELS[(i % 6) as usize])
+		},
+		_ => {
+			let level = 8 + 10 * (index - 232);
+			(level, level, level)
+		},
+	}
+}
+
+/// Returns the name of a basic color.
+///
+/// Returns `None` for the color cube and grayscale ramp.
+///
+/// ```
+/// assert_eq!(ansi_gfx::palette::palette_name(9), Some("bright_red"));
+/// assert_eq!(ansi_gfx::palette::palette_name(196), None);
+/// ```
+pub const fn palette_name(index: u8) -> Option<&'static str> {
+	if index < 16 { Some(BASIC_NAMES[index as usize]) } else { None }
+}
+
+/// Returns a legible text color for the background color.
+///
+/// Returns [`BLACK`] for light backgrounds and [`WHITE`] for dark backgrounds based on the perceived brightness (ITU-R BT.601 luma).
+///
+/// ```
+/// use ansi_gfx::palette::contrasting_fg;
+/// assert_eq!(contrasting_fg((255, 255, 0)), ansi_gfx::BLACK);
+/// assert_eq!(contrasting_fg((0, 0, 128)), ansi_gfx::WHITE);
+/// ```
+pub const fn contrasting_fg(bg: (u8, u8, u8)) -> Code {
+	let (r, g, b) = bg;
+	let luma = 299 * r as u32 + 587 * g as u32 + 114 * b as u32;
+	if luma >= 128 * 1000 { BLACK } else { WHITE }
+}
+
+/// Picks distinct colors from the color cube for categorical coloring.
+///
+/// Fills the buffer with `count` palette indices of the 6×6×6 color cube, each as far as possible from the ones before it.
+/// The first color is bright red, among equally distant colors the most saturated one is picked.
+/// At most 216 indices are produced as the colors are distinct, and no more than fit in the buffer.
+///
+/// ```
+/// let mut buf = [0u8; 4];
+/// assert_eq!(ansi_gfx::palette::palette_ramp(4, &mut buf), [196, 51, 93, 118]);
+/// ```
+pub fn palette_ramp(count: usize, out: &mut [u8]) -> &[u8] {
+	let count = count.min(out.len()).min(216);
+	// Squared distance of every cube color to the nearest color picked so far
+	let mut nearest = [u32::MAX; 216];
+	let mut pick = 196u8;
+	for slot in &mut out[..count] {
+		*slot = pick;
+		let picked = palette_to_rgb(pick);
+		let mut best = (0, 0);
+		for index in 16..=231u8 {
+			let rgb = palette_to_rgb(index);
+			let d = &mut nearest[(index - 16) as usize];
+			*d = (*d).min(distance(rgb, picked));
+			let saturation = rgb.0.max(rgb.1).max(rgb.2) - rgb.0.min(rgb.1).min(rgb.2);
+			if (*d, saturation) > best {
+				best = (*d, saturation);
+				pick = index;
+			}
+		}
+	}
+	&out[..count]
+}
+
+/// Intensity of the 88-color palette's 4×4×4 cube coordinates.
+const CUBE88_LEVELS: [u8; 4] = [0, 139, 205, 255];
+
+/// Intensity of the 88-color palette's grayscale ramp.
+const GRAY88_LEVELS: [u8; 8] = [46, 92, 115, 139, 162, 185, 208, 231];
+
+fn nearest(levels: &[u8], value: u8) -> usize {
+	let mut best = 0;
+	for i in 1..levels.len() {
+		if levels[i].abs_diff(value) < levels[best].abs_diff(value) {
+			best = i;
+		}
+	}
+	best
+}
+
+fn distance((r1, g1, b1): (u8, u8, u8), (r2, g2, b2): (u8, u8, u8)) -> u32 {
+	let dr = r1.abs_diff(r2) as u32;
+	let dg = g1.abs_diff(g2) as u32;
+	let db = b1.abs_diff(b2) as u32;
+	dr * dr + dg * dg + db * db
+}
+
+/// Returns the nearest color of the 88-color palette.
+///
+/// Some terminals, notably rxvt-unicode built with 88 colors (`TERM=rxvt-88color`), only support an 88-color palette.
+/// Prefer this over the 256-color palette when targeting those terminals, as palette indices above 87 are not available there.
+///
+/// The 88-color palette consists of the 16 basic colors (0–15), a 4×4×4 color cube (16–79) and an 8-step grayscale ramp (80–87).
+/// The basic colors are themed by the terminal and are never returned.
+///
+/// ```
+/// assert_eq!(ansi_gfx::palette::rgb_to_palette88(0, 0, 0), 16);
+/// assert_eq!(ansi_gfx::palette::rgb_to_palette88(255, 255, 255), 79);
+/// ```
+pub fn rgb_to_palette88(r: u8, g: u8, b: u8) -> u8 {
+	let (ri, gi, bi) = (nearest(&CUBE88_LEVELS, r), nearest(&CUBE88_LEVELS, g), nearest(&CUBE88_LEVELS, b));
+	let cube = (CUBE88_LEVELS[ri], CUBE88_LEVELS[gi], CUBE88_LEVELS[bi]);
+	let average = ((r as u32 + g as u32 + b as u32) / 3) as u8;
+	let gray = nearest(&GRAY88_LEVELS, average);
+	let level = GRAY88_LEVELS[gray];
+	if distance((level, level, level), (r, g, b)) < distance(cube, (r, g, b)) {
+		80 + gray as u8
+	}
+	else {
+		(16 + 16 * ri + 4 * gi + bi) as u8
+	}
+}
@@ -0,0 +1,426 @@
+use super::{control_len, sgr_sequence_len, Code, Print, MAX_SGR_PARAMS};
+
+/// Parameters of an SGR escape sequence.
+///
+/// Empty parameters are interpreted as `0`.
+#[derive(Clone, Debug)]
+pub(crate) struct Params<'a> {
+	params: Option<&'a [u8]>,
+}
+
+impl<'a> Params<'a> {
+	/// Returns the parameters of the string if it is exactly one SGR escape sequence.
+	pub fn new(s: &'a str) -> Option<Params<'a>> {
+		if sgr_sequence_len(s) != Some(s.len()) {
+			return None;
+		}
+		Some(Params { params: Some(&s.as_bytes()[2..s.len() - 1]) })
+	}
+}
+
+impl<'a> Iterator for Params<'a> {
+	/// Returns `None` for parameters which are not valid codes.
+	type Item = Option<u8>;
+	fn next(&mut self) -> Option<Option<u8>> {
+		let params = self.params?;
+		let (param, rest) = match params.iter().position(|&byte| byte == b';') {
+			Some(pos) => (&params[..pos], Some(&params[pos + 1..])),
+			None => (params, None),
+		};
+		self.params = rest;
+		let mut value = 0u32;
+		for &byte in param {
+			if !byte.is_ascii_digit() {
+				return Some(None);
+			}
+			value = value * 10 + (byte - b'0') as u32;
+			if value > 255 {
+				return Some(None);
+			}
+		}
+		Some(Some(value as u8))
+	}
+}
+
+/// Parses an SGR escape sequence into its codes.
+///
+/// The string must be exactly one SGR escape sequence with parameters separated by `;`.
+/// Empty parameters are interpreted as `0`.
+/// Returns `None` if the string is not a valid SGR escape sequence, a parameter is larger than 255 or the buffer is too small.
+///
+/// ```
+/// let mut buf = [0u8; 16];
+/// let print = ansi_gfx::parse("\x1b[1;38;5;28m", &mut buf).unwrap();
+/// assert_eq!(format!("{}", print), format!("{}", ansi_gfx::mode!(BOLD; FG PAL 28)));
+/// ```
+pub fn parse<'a>(s: &str, out: &'a mut [u8]) -> Option<Print<&'a [u8]>> {
+	let mut len = 0;
+	for param in Params::new(s)? {
+		*out.get_mut(len)? = param?;
+		len += 1;
+	}
+	Some(Print { __codes: &out[..len] })
+}
+
+/// Result of [`parse_partial`].
+#[derive(Copy, Clone, Debug)]
+pub enum ParseResult<'a> {
+	/// The codes of the SGR escape sequence and its length in bytes.
+	Complete(Print<&'a [u8]>, usize),
+	/// The input ends in the middle of an SGR escape sequence, more input is needed.
+	Incomplete,
+}
+
+/// Parses the SGR escape sequence at the start of the input for streaming.
+///
+/// Unlike [`parse`] the input may continue after the escape sequence, the number of bytes consumed is returned.
+/// If the input is a truncated SGR escape sequence (eg. split across reads) [`ParseResult::Incomplete`] is returned so the caller can buffer and resume.
+/// A truncated sequence which already exceeds [`MAX_SGR_PARAMS`] parameters is rejected instead.
+/// Returns `None` if the input does not start with an SGR escape sequence or [`parse`] fails.
+///
+/// ```
+/// use ansi_gfx::ParseResult;
+/// let mut buf = [0u8; 16];
+/// assert!(matches!(ansi_gfx::parse_partial("\x1b[1;3", &mut buf), Some(ParseResult::Incomplete)));
+/// match ansi_gfx::parse_partial("\x1b[1;31mHello", &mut buf) {
+/// 	Some(ParseResult::Complete(print, consumed)) => {
+/// 		assert_eq!(format!("{}", print), "\x1b[1;31m");
+/// 		assert_eq!(consumed, 7);
+/// 	},
+/// 	_ => unreachable!(),
+/// }
+/// ```
+pub fn parse_partial<'a>(s: &str, out: &'a mut [u8]) -> Option<ParseResult<'a>> {
+	if let Some(len) = sgr_sequence_len(s) {
+		return Some(ParseResult::Complete(parse(&s[..len], out)?, len));
+	}
+	let bytes = s.as_bytes();
+	let prefix = bytes.len() <= 2 && b"\x1b[".starts_with(bytes);
+	let truncated = bytes.starts_with(b"\x1b[")
+		&& bytes[2..].iter().all(|&byte| matches!(byte, b'0'..=b'9' | b';' | b':'))
+		&& bytes[2..].iter().filter(|&&byte| matches!(byte, b';' | b':')).count() < MAX_SGR_PARAMS;
+	if !bytes.is_empty() && (prefix || truncated) { Some(ParseResult::Incomplete) } else { None }
+}
+
+/// Logical SGR item.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum Sgr {
+	/// Single code such as an attribute, reset or named color.
+	Code(Code),
+	/// Foreground palette color (`38;5;n`).
+	ForegroundPalette(u8),
+	/// Background palette color (`48;5;n`).
+	BackgroundPalette(u8),
+	/// Foreground RGB color (`38;2;r;g;b`).
+	ForegroundRgb(u8, u8, u8),
+	/// Background RGB color (`48;2;r;g;b`).
+	BackgroundRgb(u8, u8, u8),
+}
+
+/// Parses an SGR escape sequence into logical items.
+///
+/// Unlike [`parse`] the extended colors are grouped into a single item.
+/// Returns `None` if the string is not a valid SGR escape sequence or an extended color is truncated.
+///
+/// ```
+/// use ansi_gfx::Sgr;
+/// let mut iter = ansi_gfx::parse_sgr("\x1b[1;38;2;243;159;24m").unwrap();
+/// assert_eq!(iter.next(), Some(Sgr::Code(ansi_gfx::BOLD)));
+/// assert_eq!(iter.next(), Some(Sgr::ForegroundRgb(243, 159, 24)));
+/// assert_eq!(iter.next(), None);
+/// ```
+pub fn parse_sgr(s: &str) -> Option<SgrIter<'_>> {
+	let iter = SgrIter { params: Params::new(s)? };
+	// Validate the whole sequence upfront so iteration never fails
+	let mut check = iter.clone();
+	while check.params.params.is_some() {
+		check.next_sgr()?;
+	}
+	Some(iter)
+}
+
+/// Iterator returned by [`parse_sgr`].
+#[derive(Clone, Debug)]
+pub struct SgrIter<'a> {
+	params: Params<'a>,
+}
+
+impl<'a> SgrIter<'a> {
+	fn next_sgr(&mut self) -> Option<Sgr> {
+		let code = self.params.next()??;
+		let sgr = match code {
+			38 | 48 => {
+				let fg = code == 38;
+				match self.params.next()?? {
+					5 => {
+						let index = self.params.next()??;
+						if fg { Sgr::ForegroundPalette(index) } else { Sgr::BackgroundPalette(index) }
+					},
+					2 => {
+						let red = self.params.next()??;
+						let green = self.params.next()??;
+						let blue = self.params.next()??;
+						if fg { Sgr::ForegroundRgb(red, green, blue) } else { Sgr::BackgroundRgb(red, green, blue) }
+					},
+					_ => return None,
+				}
+			},
+			_ => Sgr::Code(Code { __byte: code }),
+		};
+		Some(sgr)
+	}
+}
+
+impl<'a> Iterator for SgrIter<'a> {
+	type Item = Sgr;
+	#[inline]
+	fn next(&mut self) -> Option<Sgr> {
+		self.next_sgr()
+	}
+}
+
+/// Token of styled text returned by [`events`].
+#[derive(Copy, Clone, Debug)]
+pub enum Event<'a> {
+	/// SGR escape sequence.
+	Style(Print<&'a [u8]>),
+	/// Text without escape sequences.
+	Text(&'a str),
+	/// Any other escape sequence or an SGR escape sequence which cannot be parsed, see [`sanitize`](super::sanitize) for how its extent is determined.
+	Other(&'a str),
+}
+
+/// Tokenizes styled text into styles, text and other escape sequences.
+///
+/// Every byte of the input is part of exactly one event, concatenating the text and escape sequences reproduces the input.
+/// Unterminated escape sequences extend to the end of the input.
+///
+/// The codes of the styles are parsed into the buffer, a buffer as long as the input is always large enough.
+/// SGR escape sequences whose codes no longer fit are returned as [`Event::Other`].
+///
+/// ```
+/// use ansi_gfx::Event;
+/// let s = "\x1b[1mhi\x1b[2J";
+/// let mut buf = [0u8; 16];
+/// let mut iter = ansi_gfx::events(s, &mut buf);
+/// assert!(matches!(iter.next(), Some(Event::Style(print)) if print.bytes() == [1]));
+/// assert!(matches!(iter.next(), Some(Event::Text("hi"))));
+/// assert!(matches!(iter.next(), Some(Event::Other("\x1b[2J"))));
+/// assert!(iter.next().is_none());
+/// ```
+#[inline]
+pub fn events<'a>(s: &'a str, buf: &'a mut [u8]) -> Events<'a> {
+	Events { s, buf }
+}
+
+/// Iterator returned by [`events`].
+#[derive(Debug)]
+pub struct Events<'a> {
+	s: &'a str,
+	buf: &'a mut [u8],
+}
+
+impl<'a> Iterator for Events<'a> {
+	type Item = Event<'a>;
+	fn next(&mut self) -> Option<Event<'a>> {
+		if self.s.is_empty() {
+			return None;
+		}
+		let bytes = self.s.as_bytes();
+		if bytes[0] != 0x1b {
+			let len = bytes.iter().position(|&byte| byte == 0x1b).unwrap_or(bytes.len());
+			let (text, rest) = self.s.split_at(len);
+			self.s = rest;
+			return Some(Event::Text(text));
+		}
+		if let Some(len) = sgr_sequence_len(self.s) {
+			let (sequence, rest) = self.s.split_at(len);
+			let count = parse(sequence, self.buf).map(|print| print.__codes.len());
+			if let Some(count) = count {
+				// Hand out the parsed codes and keep the rest of the buffer for the next styles
+				let (codes, buf) = core::mem::take(&mut self.buf).split_at_mut(count);
+				self.buf = buf;
+				self.s = rest;
+				return Some(Event::Style(Print { __codes: codes }));
+			}
+		}
+		// Never split a multibyte character following a malformed sequence
+		let mut len = control_len(bytes);
+		while !self.s.is_char_boundary(len) {
+			len += 1;
+		}
+		let (sequence, rest) = self.s.split_at(len);
+		self.s = rest;
+		Some(Event::Other(sequence))
+	}
+}
+
+/// Event returned by [`SgrParser::feed`].
+#[derive(Copy, Clone, Debug)]
+pub enum ParsedEvent<'a> {
+	/// Complete SGR escape sequence.
+	Style(Print<&'a [u8]>),
+	/// Run of text.
+	///
+	/// A run ends when an escape sequence starts, at [`SgrParser::finish`] or after 64 bytes.
+	/// Runs split for length never split a UTF-8 encoded character.
+	Text(&'a [u8]),
+	/// Escape sequence which is not an SGR escape sequence or cannot be parsed.
+	///
+	/// Only the `ESC [` parameters and final byte are included, the rest of a longer escape sequence follows as text.
+	Other(&'a [u8]),
+}
+
+/// Streaming SGR parser for incremental input.
+///
+/// Bytes are fed one at a time and SGR escape sequences split across reads are recognized, the streaming counterpart of [`parse`].
+/// Text is buffered into runs, call [`finish`](SgrParser::finish) at the end of the input for the last run.
+/// Escape sequences longer than 64 bytes are returned as [`ParsedEvent::Other`].
+///
+/// ```
+/// use ansi_gfx::{ParsedEvent, SgrParser};
+/// let mut parser = SgrParser::new();
+/// let mut styles = Vec::new();
+/// for &byte in b"\x1b[1;3" {
+/// 	assert!(parser.feed(byte).is_none());
+/// }
+/// if let Some(ParsedEvent::Style(print)) = parser.feed(b'm') {
+/// 	styles.push(print.to_string());
+/// }
+/// assert_eq!(styles, ["\x1b[1;3m"]);
+/// for &byte in "héllo".as_bytes() {
+/// 	assert!(parser.feed(byte).is_none());
+/// }
+/// assert!(matches!(parser.finish(), Some(ParsedEvent::Text(b"h\xc3\xa9llo"))));
+/// ```
+#[derive(Clone, Debug)]
+pub struct SgrParser {
+	pending: [u8; 64],
+	len: usize,
+	codes: [u8; 64],
+	text: [u8; 64],
+	text_len: usize,
+	// Text bytes handed out by the last event, removed on the next call
+	flushed: usize,
+	// Byte which ended the previous event, it is fed again first
+	carry: Option<u8>,
+}
+
+impl Default for SgrParser {
+	#[inline]
+	fn default() -> SgrParser {
+		SgrParser::new()
+	}
+}
+
+impl SgrParser {
+	/// Creates a parser.
+	#[inline]
+	pub const fn new() -> SgrParser {
+		SgrParser { pending: [0; 64], len: 0, codes: [0; 64], text: [0; 64], text_len: 0, flushed: 0, carry: None }
+	}
+
+	/// Applies the work deferred while the previous event borrowed the buffers.
+	fn catch_up(&mut self) {
+		if self.flushed > 0 {
+			self.text.copy_within(self.flushed..self.text_len, 0);
+			self.text_len -= self.flushed;
+			self.flushed = 0;
+		}
+		// The carried byte never produces an event, the text or escape sequence before it was just returned
+		match self.carry.take() {
+			Some(0x1b) => {
+				self.pending[0] = 0x1b;
+				self.len = 1;
+			},
+			Some(byte) => {
+				self.text[self.text_len] = byte;
+				self.text_len += 1;
+			},
+			None => (),
+		}
+	}
+
+	/// Feeds the next byte.
+	///
+	/// Returns `None` while an escape sequence or run of text is incomplete.
+	pub fn feed(&mut self, byte: u8) -> Option<ParsedEvent<'_>> {
+		self.catch_up();
+		let len = self.len;
+		if len == 0 {
+			if byte == 0x1b && self.text_len > 0 {
+				self.carry = Some(byte);
+				let text_len = self.text_len;
+				self.text_len = 0;
+				return Some(ParsedEvent::Text(&self.text[..text_len]));
+			}
+			if byte == 0x1b {
+				self.pending[0] = byte;
+				self.len = 1;
+				return None;
+			}
+			if self.text_len == self.text.len() {
+				// Keep an incomplete UTF-8 encoded character for the next run
+				let text = &self.text[..self.text_len];
+				let keep = match text.iter().rev().take(4).position(|&byte| byte & 0xc0 != 0x80) {
+					Some(pos) if utf8_len(text[text.len() - 1 - pos]) > pos + 1 => pos + 1,
+					_ => 0,
+				};
+				self.flushed = text.len() - keep;
+				self.carry = Some(byte);
+				return Some(ParsedEvent::Text(&self.text[..self.flushed]));
+			}
+			self.text[self.text_len] = byte;
+			self.text_len += 1;
+			return None;
+		}
+		if byte == 0x1b {
+			self.carry = Some(byte);
+			self.len = 0;
+			return Some(ParsedEvent::Other(&self.pending[..len]));
+		}
+		// There is always room for the final byte
+		self.pending[len] = byte;
+		let continues = match len {
+			1 => byte == b'[',
+			_ => matches!(byte, b'0'..=b'9' | b';' | b':'),
+		};
+		if continues && len + 2 < self.pending.len() {
+			self.len = len + 1;
+			return None;
+		}
+		self.len = 0;
+		let sequence = &self.pending[..len + 1];
+		if byte == b'm' && len >= 2 {
+			// The pending bytes are always ASCII
+			let s = unsafe { core::str::from_utf8_unchecked(sequence) };
+			if let Some(print) = parse(s, &mut self.codes) {
+				return Some(ParsedEvent::Style(print));
+			}
+		}
+		Some(ParsedEvent::Other(sequence))
+	}
+
+	/// Returns the buffered run of text or incomplete escape sequence and resets the parser, eg. at the end of the input.
+	pub fn finish(&mut self) -> Option<ParsedEvent<'_>> {
+		self.catch_up();
+		if self.text_len > 0 {
+			let text_len = self.text_len;
+			self.text_len = 0;
+			return Some(ParsedEvent::Text(&self.text[..text_len]));
+		}
+		let len = self.len;
+		self.len = 0;
+		if len > 0 { Some(ParsedEvent::Other(&self.pending[..len])) } else { None }
+	}
+}
+
+/// Returns the length of the UTF-8 encoded character starting with the byte.
+fn utf8_len(lead: u8) -> usize {
+	match lead {
+		0xc0..=0xdf => 2,
+		0xe0..=0xef => 3,
+		0xf0..=0xf7 => 4,
+		_ => 1,
+	}
+}
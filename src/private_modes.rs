@@ -0,0 +1,49 @@
+/*!
+DEC private mode toggles for full-screen applications.
+
+Each type formats as `ESC [ ? n h` when enabled and `ESC [ ? n l` when disabled.
+
+```
+use ansi_gfx::private_modes::{AlternateScreen, BracketedPaste};
+assert_eq!(format!("{}", AlternateScreen(true)), "\x1b[?1049h");
+assert_eq!(format!("{}", BracketedPaste(false)), "\x1b[?2004l");
+```
+*/
+
+use core::fmt;
+use super::write_params;
+
+macro_rules! private_mode {
+	($(#[$meta:meta])* $name:ident = $mode:literal) => {
+		$(#[$meta])*
+		#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+		#[must_use]
+		pub struct $name(pub bool);
+
+		impl fmt::Display for $name {
+			#[inline]
+			fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+				f.write_str("\x1b[?")?;
+				let mode: u16 = $mode;
+				write_params(&[mode], if self.0 { b'h' } else { b'l' }, f)
+			}
+		}
+	};
+}
+
+private_mode! {
+	/// Switches to the alternate screen buffer and back (mode 1049).
+	AlternateScreen = 1049
+}
+private_mode! {
+	/// Enables or disables cursor blinking (mode 12).
+	CursorBlink = 12
+}
+private_mode! {
+	/// Enables or disables bracketed paste (mode 2004).
+	BracketedPaste = 2004
+}
+private_mode! {
+	/// Enables or disables reporting mouse button presses (mode 1000).
+	MouseTracking = 1000
+}
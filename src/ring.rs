@@ -0,0 +1,123 @@
+use core::fmt;
+
+/// What a [`RingWriter`] does when a write does not fit.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum Overflow {
+	/// Drops the oldest bytes to make room, the write always succeeds.
+	///
+	/// The oldest remaining bytes may start in the middle of an escape sequence.
+	DropOldest,
+	/// Fails the write with [`fmt::Error`] without writing anything.
+	///
+	/// A failed `write!` is rolled back entirely, a style is never left half written.
+	Error,
+}
+
+/// Ring buffer implementing [`fmt::Write`].
+///
+/// Styles and text can be formatted into the ring buffer without `alloc` while a consumer drains it.
+///
+/// ```
+/// use std::fmt::Write;
+/// use ansi_gfx::{Overflow, RingWriter};
+///
+/// let mut buf = [0u8; 16];
+/// let mut ring = RingWriter::new(&mut buf, Overflow::Error);
+/// write!(ring, "{}hi", ansi_gfx::RED).unwrap();
+/// assert_eq!(ring.as_slices(), (&b"\x1b[31mhi"[..], &b""[..]));
+/// ring.consume(5);
+/// assert_eq!(ring.as_slices(), (&b"hi"[..], &b""[..]));
+/// ```
+pub struct RingWriter<'a> {
+	buf: &'a mut [u8],
+	start: usize,
+	len: usize,
+	overflow: Overflow,
+}
+
+impl<'a> RingWriter<'a> {
+	/// Creates an empty ring buffer.
+	#[inline]
+	pub fn new(buf: &'a mut [u8], overflow: Overflow) -> RingWriter<'a> {
+		RingWriter { buf, start: 0, len: 0, overflow }
+	}
+
+	/// Returns the capacity in bytes.
+	#[inline]
+	pub fn capacity(&self) -> usize {
+		self.buf.len()
+	}
+
+	/// Returns the number of buffered bytes.
+	#[inline]
+	pub fn len(&self) -> usize {
+		self.len
+	}
+
+	/// Returns true if nothing is buffered.
+	#[inline]
+	pub fn is_empty(&self) -> bool {
+		self.len == 0
+	}
+
+	/// Returns the buffered bytes, oldest first, as two slices.
+	pub fn as_slices(&self) -> (&[u8], &[u8]) {
+		let end = self.start + self.len;
+		if end <= self.buf.len() {
+			(&self.buf[self.start..end], &[])
+		}
+		else {
+			(&self.buf[self.start..], &self.buf[..end - self.buf.len()])
+		}
+	}
+
+	/// Removes the oldest bytes, eg. after they were sent to the display.
+	pub fn consume(&mut self, n: usize) {
+		let n = n.min(self.len);
+		self.start = if self.buf.is_empty() { 0 } else { (self.start + n) % self.buf.len() };
+		self.len -= n;
+	}
+
+	/// Removes all buffered bytes.
+	#[inline]
+	pub fn clear(&mut self) {
+		self.start = 0;
+		self.len = 0;
+	}
+}
+
+impl<'a> fmt::Write for RingWriter<'a> {
+	fn write_str(&mut self, s: &str) -> fmt::Result {
+		let cap = self.buf.len();
+		let mut bytes = s.as_bytes();
+		if self.len + bytes.len() > cap {
+			match self.overflow {
+				Overflow::Error => return Err(fmt::Error),
+				Overflow::DropOldest => {
+					if bytes.len() >= cap {
+						bytes = &bytes[bytes.len() - cap..];
+						self.clear();
+					}
+					else {
+						self.consume(self.len + bytes.len() - cap);
+					}
+				},
+			}
+		}
+		for &byte in bytes {
+			let i = (self.start + self.len) % cap;
+			self.buf[i] = byte;
+			self.len += 1;
+		}
+		Ok(())
+	}
+
+	fn write_fmt(&mut self, args: fmt::Arguments) -> fmt::Result {
+		let len = self.len;
+		let result = fmt::write(self, args);
+		if result.is_err() {
+			self.len = len;
+		}
+		result
+	}
+}
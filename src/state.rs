@@ -0,0 +1,185 @@
+/*!
+Logical style state of a code list.
+
+Applying a code list to a [`State`] resolves its net effect: duplicate codes collapse and the last color wins.
+Two code lists with equal states are visually identical.
+
+Starting from [`State::CLEARED`] instead tracks the absolute style of a terminal.
+*/
+
+use core::fmt;
+use super::{segments, Code, Params};
+
+/// Single logical segment, a code or an extended color.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub(crate) struct Segment {
+	bytes: [u8; 5],
+	len: u8,
+}
+
+impl Segment {
+	pub fn new(segment: &[u8]) -> Segment {
+		let mut bytes = [0u8; 5];
+		let len = segment.len().min(5);
+		bytes[..len].copy_from_slice(&segment[..len]);
+		Segment { bytes, len: len as u8 }
+	}
+
+	pub fn as_bytes(&self) -> &[u8] {
+		&self.bytes[..self.len as usize]
+	}
+}
+
+/// Attribute reset codes and the attribute codes they turn off.
+const ATTR_RESETS: [(u8, u16); 7] = [
+	(22, 1 << 1 | 1 << 2),
+	(23, 1 << 3),
+	(24, 1 << 4),
+	(25, 1 << 5 | 1 << 6),
+	(27, 1 << 7),
+	(28, 1 << 8),
+	(29, 1 << 9),
+];
+
+/// Net effect of a code list.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub(crate) struct State {
+	/// Starts with a full reset.
+	pub reset: bool,
+	/// Attributes turned on, bit `n` for code `n`.
+	pub on: u16,
+	/// Attributes turned off, bit `n` for code `n`.
+	pub off: u16,
+	/// Foreground color.
+	pub fg: Option<Segment>,
+	/// Background color.
+	pub bg: Option<Segment>,
+	/// Any other codes.
+	pub other: [u64; 4],
+}
+
+impl State {
+	/// State of a terminal with the default style.
+	pub const CLEARED: State = State { reset: true, on: 0, off: 0, fg: None, bg: None, other: [0; 4] };
+
+	/// Returns true if nothing is styled.
+	pub fn is_cleared(&self) -> bool {
+		*self == State::CLEARED
+	}
+
+	pub fn new(codes: &[u8]) -> State {
+		let mut state = State::default();
+		for segment in segments(codes) {
+			state.apply(segment);
+		}
+		state
+	}
+
+	pub fn apply(&mut self, segment: &[u8]) {
+		let code = Code { __byte: segment[0] };
+		match code.__byte {
+			0 => *self = State { reset: true, ..State::default() },
+			n @ 1..=9 => {
+				self.on |= 1 << n;
+				self.off &= !(1 << n);
+			},
+			n if code.is_foreground() => {
+				self.fg = if self.reset && n == 39 { None } else { Some(Segment::new(segment)) };
+			},
+			n if code.is_background() => {
+				self.bg = if self.reset && n == 49 { None } else { Some(Segment::new(segment)) };
+			},
+			n => match ATTR_RESETS.iter().find(|&&(reset, _)| reset == n) {
+				Some(&(_, mask)) => {
+					self.on &= !mask;
+					if !self.reset {
+						self.off |= mask;
+					}
+				},
+				None => self.other[n as usize / 64] |= 1 << (n % 64),
+			},
+		}
+	}
+
+	/// Calls the closure with the segments of a minimal code list producing this state.
+	///
+	/// The order is the reset, attribute resets, attributes, foreground color, background color and any other codes.
+	/// Attribute resets come first so they don't cancel an attribute sharing the same reset code.
+	#[cfg(feature = "alloc")]
+	pub fn for_each_segment(&self, mut f: impl FnMut(&[u8])) {
+		if self.reset {
+			f(&[0]);
+		}
+		for &(reset, mask) in &ATTR_RESETS {
+			if self.off & mask != 0 {
+				f(&[reset]);
+			}
+		}
+		for n in 1..=9u8 {
+			if self.on & 1 << n != 0 {
+				f(&[n]);
+			}
+		}
+		for color in [self.fg, self.bg].iter().flatten() {
+			f(color.as_bytes());
+		}
+		for n in 0..=255u8 {
+			if self.other[n as usize / 64] & 1 << (n % 64) != 0 {
+				f(&[n]);
+			}
+		}
+	}
+
+	/// Writes a single SGR escape sequence which sets this state starting from a full reset.
+	pub fn write_fresh<W: fmt::Write + ?Sized>(&self, w: &mut W) -> fmt::Result {
+		w.write_str("\x1b[0")?;
+		for n in 1..=9u8 {
+			if self.on & 1 << n != 0 {
+				write!(w, ";{}", n)?;
+			}
+		}
+		for color in [self.fg, self.bg].iter().flatten() {
+			for byte in color.as_bytes() {
+				write!(w, ";{}", byte)?;
+			}
+		}
+		for n in 0..=255u8 {
+			if self.other[n as usize / 64] & 1 << (n % 64) != 0 {
+				write!(w, ";{}", n)?;
+			}
+		}
+		w.write_str("m")
+	}
+
+	/// Applies the codes of an SGR escape sequence.
+	///
+	/// Stops at the first invalid parameter.
+	pub fn apply_sequence(&mut self, sequence: &str) {
+		let Some(params) = Params::new(sequence) else { return };
+		let mut buf = [0u8; 5];
+		let mut len = 0;
+		for param in params {
+			let Some(code) = param else { return };
+			if len == buf.len() {
+				// Malformed extended color swallowing the rest, same as `segments`
+				continue;
+			}
+			buf[len] = code;
+			len += 1;
+			let need = match buf[..len] {
+				[38 | 48] => continue,
+				[38 | 48, 5, ..] => 3,
+				[38 | 48, 2, ..] => 5,
+				[38 | 48, ..] => continue,
+				_ => 1,
+			};
+			if len == need {
+				self.apply(&buf[..len]);
+				len = 0;
+			}
+		}
+		if len > 0 {
+			self.apply(&buf[..len]);
+		}
+	}
+}
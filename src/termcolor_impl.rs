@@ -0,0 +1,99 @@
+use termcolor::{Color, ColorSpec};
+use super::{segments, Print, PrintBuf};
+
+const NAMED: [Color; 8] = [Color::Black, Color::Red, Color::Green, Color::Yellow, Color::Blue, Color::Magenta, Color::Cyan, Color::White];
+
+fn to_color(segment: &[u8]) -> Option<Color> {
+	match *segment {
+		[code @ (30..=37 | 40..=47)] => Some(NAMED[(code % 10) as usize]),
+		[code @ (90..=97 | 100..=107)] => Some(Color::Ansi256(8 + code % 10)),
+		[_, 5, index] => Some(Color::Ansi256(index)),
+		[_, 2, red, green, blue] => Some(Color::Rgb(red, green, blue)),
+		_ => None,
+	}
+}
+
+fn from_color(color: &Color, ground: u8, codes: &mut PrintBuf) {
+	match *color {
+		Color::Ansi256(index) => codes.__codes.extend_from_slice(&[ground + 8, 5, index]),
+		Color::Rgb(red, green, blue) => codes.__codes.extend_from_slice(&[ground + 8, 2, red, green, blue]),
+		ref color => if let Some(i) = NAMED.iter().position(|named| named == color) {
+			codes.__codes.push(ground + i as u8);
+		},
+	}
+}
+
+impl<T: AsRef<[u8]>> Print<T> {
+	/// Converts the style to a `termcolor` color spec.
+	///
+	/// The codes are applied in order, codes without a `termcolor` equivalent are ignored.
+	/// Bright colors are converted to their palette index.
+	/// The spec's reset flag is set only if the style contains a [`RESET`](super::RESET).
+	///
+	/// ```
+	/// let spec = ansi_gfx::mode!(BOLD; RED).to_color_spec();
+	/// assert!(spec.bold());
+	/// assert_eq!(spec.fg(), Some(&termcolor::Color::Red));
+	/// ```
+	pub fn to_color_spec(&self) -> ColorSpec {
+		let mut spec = ColorSpec::new();
+		spec.set_reset(false);
+		for segment in segments(self.__codes.as_ref()) {
+			match segment[0] {
+				0 => {
+					spec.clear();
+					spec.set_reset(true);
+				},
+				1 => { spec.set_bold(true); },
+				2 => { spec.set_dimmed(true); },
+				3 => { spec.set_italic(true); },
+				4 => { spec.set_underline(true); },
+				9 => { spec.set_strikethrough(true); },
+				22 => { spec.set_bold(false).set_dimmed(false); },
+				23 => { spec.set_italic(false); },
+				24 => { spec.set_underline(false); },
+				29 => { spec.set_strikethrough(false); },
+				30..=38 | 90..=97 => { spec.set_fg(to_color(segment)); },
+				39 => { spec.set_fg(None); },
+				40..=48 | 100..=107 => { spec.set_bg(to_color(segment)); },
+				49 => { spec.set_bg(None); },
+				_ => (),
+			}
+		}
+		spec
+	}
+}
+
+impl PrintBuf {
+	/// Converts a `termcolor` color spec to a style.
+	///
+	/// If the spec's reset flag is set the style starts with a [`RESET`](super::RESET).
+	/// Intense named foreground colors are converted to their bright variant.
+	///
+	/// ```
+	/// let mut spec = termcolor::ColorSpec::new();
+	/// spec.set_bold(true).set_fg(Some(termcolor::Color::Red)).set_reset(false);
+	/// assert_eq!(format!("{}", ansi_gfx::PrintBuf::from_color_spec(&spec)), "\x1b[1;31m");
+	/// ```
+	pub fn from_color_spec(spec: &ColorSpec) -> PrintBuf {
+		let mut codes = PrintBuf::new();
+		let attrs = [(spec.reset(), 0), (spec.bold(), 1), (spec.dimmed(), 2), (spec.italic(), 3), (spec.underline(), 4), (spec.strikethrough(), 9)];
+		for &(set, code) in &attrs {
+			if set {
+				codes.__codes.push(code);
+			}
+		}
+		if let Some(fg) = spec.fg() {
+			from_color(fg, 30, &mut codes);
+			if spec.intense() {
+				if let Some(last @ 30..=37) = codes.__codes.last_mut() {
+					*last += 60;
+				}
+			}
+		}
+		if let Some(bg) = spec.bg() {
+			from_color(bg, 40, &mut codes);
+		}
+		codes
+	}
+}
@@ -11,3 +11,1542 @@ fn mode() {
 	let style = UNDERLINE;
 	assert_eq!(format!("{}", mode!(BOLD; {style}; FG PAL 9; BG RGB 255, 0, 0)), "\u{1b}[1;4;38;5;9;48;2;255;0;0m");
 }
+
+#[test]
+fn classify() {
+	assert!(RED.is_foreground() && !RED.is_background() && !RED.is_attribute());
+	assert!(BRIGHT_BLUE_BG.is_background() && !BRIGHT_BLUE_BG.is_foreground());
+	assert!(BOLD.is_attribute() && RESET_STRIKE.is_attribute());
+	assert!(!RESET.is_attribute() && !RESET.is_foreground() && !RESET.is_background());
+}
+
+#[test]
+fn strip_colors_attributes() {
+	let mut buf = [0u8; 64];
+	assert_eq!(strip_colors(&mode!(BOLD; RED).__codes, &mut buf), Some("\x1b[1m"));
+	assert_eq!(strip_colors(&mode!(FG RGB 1, 2, 3; UNDERLINE; BG PAL 5).__codes, &mut buf), Some("\x1b[4m"));
+	assert_eq!(strip_colors(&mode!(RED; BLUE_BG).__codes, &mut buf), Some(""));
+	assert_eq!(strip_attributes(&mode!(BOLD; FG PAL 2; UNDERLINE; BLUE_BG).__codes, &mut buf), Some("\x1b[38;5;2;44m"));
+	assert_eq!(strip_attributes(&mode!(BOLD; RED).__codes, &mut [0u8; 4]), None);
+}
+
+#[test]
+fn write_codes_unbounded() {
+	use core::fmt::Write;
+	let mut s = String::new();
+	write_codes(&mode!(BOLD; FG RGB 255, 0, 0).__codes, &mut s).unwrap();
+	assert_eq!(s, format!("{}", mode!(BOLD; FG RGB 255, 0, 0)));
+
+	s.clear();
+	write!(s, "{}", RED).unwrap();
+	assert_eq!(s, "\x1b[31m");
+
+	// Longer than any fixed size buffer would allow
+	let codes = [107u8; 40];
+	s.clear();
+	write_codes(&codes, &mut s).unwrap();
+	assert_eq!(s, format!("\x1b[{}107m", "107;".repeat(39)));
+
+	s.clear();
+	write_codes(&[], &mut s).unwrap();
+	assert_eq!(s, "");
+}
+
+#[test]
+fn three_digits() {
+	assert_eq!(format!("{}", BRIGHT_BLACK_BG), "\x1b[100m");
+	assert_eq!(format!("{}", mode!(BG PAL 205)), "\x1b[48;5;205m");
+}
+
+#[test]
+fn debug_forms() {
+	assert_eq!(format!("{:?}", BOLD), r#""\x1b[1m""#);
+	assert_eq!(format!("{:#?}", BOLD), r#""\x1b\x5b\x31\x6d""#);
+	assert_eq!(format!("{:?}", mode!(BOLD; BRIGHT_RED)), r#""\x1b[1;91m""#);
+	assert_eq!(format!("{:#?}", mode!(BOLD; BRIGHT_RED)), r#""\x1b\x5b\x31\x3b\x39\x31\x6d""#);
+	assert_eq!("\x1b\x5b\x31\x3b\x39\x31\x6d", format!("{}", mode!(BOLD; BRIGHT_RED)));
+}
+
+#[test]
+fn palette_cube() {
+	assert_eq!(palette::cube_color(5, 5, 5), 231);
+	assert_eq!(palette::cube_color(1, 2, 3), 16 + 36 + 12 + 3);
+	assert_eq!(palette::cube_color(9, 200, 6), 231);
+	for index in 0..=255u8 {
+		match palette::cube_coords(index) {
+			Some((r, g, b)) => assert_eq!(palette::cube_color(r, g, b), index),
+			None => assert!(!(16..=231).contains(&index)),
+		}
+	}
+}
+
+#[test]
+fn terminfo_names() {
+	let table = [
+		(RESET, Some("sgr0")),
+		(BOLD, Some("bold")),
+		(DIM, Some("dim")),
+		(ITALIC, Some("sitm")),
+		(UNDERLINE, Some("smul")),
+		(BLINK, Some("blink")),
+		(INVERSE, Some("rev")),
+		(HIDDEN, Some("invis")),
+		(STRIKE, Some("smxx")),
+		(RESET_ITALIC, Some("ritm")),
+		(RESET_UNDERLINE, Some("rmul")),
+		(RESET_STRIKE, Some("rmxx")),
+		(RESET_WEIGHT, None),
+		(RED, None),
+		(DEFAULT_BG, None),
+	];
+	for &(code, name) in &table {
+		assert_eq!(terminfo_name(code), name, "{:?}", code);
+	}
+}
+
+#[test]
+fn style_eq() {
+	assert!(mode!(BOLD; RED).style_eq(&mode!(RED; BOLD)));
+	assert!(mode!(BOLD; UNDERLINE; FG PAL 3).style_eq(&mode!(FG PAL 3; UNDERLINE; BOLD; UNDERLINE)));
+	assert!(mode!(BLUE; FG RGB 1, 2, 3).style_eq(&mode!(FG RGB 1, 2, 3)));
+	assert!(mode!(BOLD; RESET_WEIGHT; RED).style_eq(&mode!(RED; RESET_WEIGHT)));
+	assert!(mode!(BOLD; RESET; RED).style_eq(&mode!(RESET; RED)));
+
+	assert!(!mode!(BOLD; RED).style_eq(&mode!(BOLD; BLUE)));
+	assert!(!mode!(RED; BLUE).style_eq(&mode!(BLUE; RED)));
+	assert!(!mode!(RED).style_eq(&mode!(RESET; RED)));
+	assert!(!mode!(FG PAL 1).style_eq(&mode!(BG PAL 1)));
+}
+
+#[test]
+fn palette_kinds() {
+	use palette::{palette_kind, PaletteKind};
+	assert_eq!(palette_kind(0), PaletteKind::Basic);
+	assert_eq!(palette_kind(15), PaletteKind::Basic);
+	assert_eq!(palette_kind(16), PaletteKind::Cube);
+	assert_eq!(palette_kind(231), PaletteKind::Cube);
+	assert_eq!(palette_kind(232), PaletteKind::Grayscale);
+	assert_eq!(palette_kind(255), PaletteKind::Grayscale);
+}
+
+#[test]
+fn sgr_sequences() {
+	assert_eq!(sgr_sequence_len("\x1b[m"), Some(3));
+	assert_eq!(sgr_sequence_len("\x1b[38:5:1mx"), Some(9));
+	assert_eq!(sgr_sequence_len("\x1b[2J"), None);
+	assert_eq!(sgr_sequence_len("\x1b["), None);
+	assert_eq!(sgr_sequence_len(""), None);
+}
+
+#[test]
+fn protect_style() {
+	let style = mode!(BOLD; BLUE_BG);
+	let content = "a\x1b[0mb\x1b[mc\x1b[22md\x1b[0;31me\x1b[31;0;38;5;0;4mf\x1b[38;5;0mg\x1b";
+	assert_eq!(
+		format!("{}", protect(style.erase(), content)),
+		"a\x1b[0m\x1b[1;44mb\x1b[m\x1b[1;44mc\x1b[22md\x1b[0;31m\x1b[1;44m\x1b[31me\x1b[31;0;38;5;0;4m\x1b[1;44m\x1b[38;5;0;4mf\x1b[38;5;0mg\x1b");
+	assert_eq!(format!("{}", protect(mode!(BOLD; RED).erase(), "x\x1b[0;31my")), "x\x1b[0;31m\x1b[1;31m\x1b[31my");
+	assert_eq!(format!("{}", protect(style.erase(), "plain")), "plain");
+}
+
+#[test]
+fn render_all_prints() {
+	let prints = [mode!(BOLD).erase(), mode!(RED).erase()];
+	let mut buf = [0u8; 64];
+	let expected = format!("{}{}", prints[0], prints[1]);
+	assert_eq!(render_all(&prints, &mut buf), Some(&expected[..]));
+	assert_eq!(render_all(&prints, &mut buf[..expected.len()]), Some(&expected[..]));
+	assert_eq!(render_all(&prints, &mut buf[..expected.len() - 1]), None);
+	assert_eq!(render_all(&[], &mut buf), Some(""));
+}
+
+#[test]
+fn contrasting_colors() {
+	use palette::{contrasting_fg, palette_to_rgb};
+	assert_eq!(contrasting_fg((0, 0, 0)), WHITE);
+	assert_eq!(contrasting_fg((40, 40, 90)), WHITE);
+	assert_eq!(contrasting_fg((255, 255, 255)), BLACK);
+	assert_eq!(contrasting_fg((200, 200, 120)), BLACK);
+	assert_eq!(palette_to_rgb(16), (0, 0, 0));
+	assert_eq!(palette_to_rgb(231), (255, 255, 255));
+	assert_eq!(palette_to_rgb(255), (238, 238, 238));
+	assert_eq!(contrasting_fg(palette_to_rgb(17)), WHITE);
+	assert_eq!(contrasting_fg(palette_to_rgb(226)), BLACK);
+}
+
+#[test]
+fn parse_codes() {
+	let mut buf = [0u8; 16];
+	assert_eq!(parse("\x1b[1;31m", &mut buf).unwrap().__codes, &[1, 31]);
+	assert_eq!(parse("\x1b[m", &mut buf).unwrap().__codes, &[0]);
+	assert_eq!(parse("\x1b[;4m", &mut buf).unwrap().__codes, &[0, 4]);
+	assert!(parse("\x1b[256m", &mut buf).is_none());
+	assert!(parse("\x1b[4:3m", &mut buf).is_none());
+	assert!(parse("\x1b[1mx", &mut buf).is_none());
+	assert!(parse("\x1b[1;2;3m", &mut buf[..2]).is_none());
+}
+
+#[test]
+fn parse_sgr_items() {
+	let items: Vec<Sgr> = parse_sgr("\x1b[0;1;38;2;1;2;3;44;48;5;200;38;5;9m").unwrap().collect();
+	assert_eq!(items, [
+		Sgr::Code(RESET),
+		Sgr::Code(BOLD),
+		Sgr::ForegroundRgb(1, 2, 3),
+		Sgr::Code(BLUE_BG),
+		Sgr::BackgroundPalette(200),
+		Sgr::ForegroundPalette(9),
+	]);
+	assert!(parse_sgr("\x1b[38;2;1;2m").is_none());
+	assert!(parse_sgr("\x1b[48;5m").is_none());
+	assert!(parse_sgr("\x1b[38;7;1m").is_none());
+	assert!(parse_sgr("\x1b[1;300m").is_none());
+}
+
+#[test]
+fn inline_str() {
+	let style = mode!(BOLD; FG RGB 255, 100, 0; BRIGHT_WHITE_BG);
+	let s: InlineStr<{ Print::<[u8; 7]>::MAX_LEN }> = style.to_inline();
+	assert_eq!(s.as_str(), format!("{}", style));
+	assert_eq!(s.len(), 23);
+	assert!(s.starts_with("\x1b[1;"));
+	assert_eq!(Print::<[u8; 0]>::MAX_LEN, 0);
+	assert_eq!(Print::<[u8; 7]>::CODE_COUNT, style.__codes.len());
+	assert_eq!(Print::<[u8; 0]>::CODE_COUNT, 0);
+	assert_eq!(&*mode!().to_inline::<0>(), "");
+}
+
+#[test]
+fn reset_for_codes() {
+	assert_eq!(BOLD.reset_for(), Some(RESET_WEIGHT));
+	assert_eq!(DIM.reset_for(), Some(RESET_WEIGHT));
+	assert_eq!(STRIKE.reset_for(), Some(RESET_STRIKE));
+	assert_eq!(BRIGHT_CYAN.reset_for(), Some(DEFAULT));
+	assert_eq!(BRIGHT_CYAN_BG.reset_for(), Some(DEFAULT_BG));
+	assert_eq!(RESET.reset_for(), None);
+	assert_eq!(DEFAULT.reset_for(), None);
+}
+
+#[test]
+fn reset_codes() {
+	assert_eq!(BOLD.reset(), RESET_WEIGHT);
+	assert_eq!(RED.reset(), DEFAULT);
+	assert_eq!(GREEN_BG.reset(), DEFAULT_BG);
+	assert_eq!(DEFAULT.reset(), RESET);
+	assert_eq!(RESET.reset(), RESET);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn print_buf_toggle() {
+	let mut style = PrintBuf::new();
+	style.push(BOLD);
+	style.__codes.extend_from_slice(&mode!(FG PAL 1).__codes);
+	let original = style.clone();
+	style.toggle(BOLD);
+	assert_eq!(style.__codes, [22, 38, 5, 1]);
+	style.toggle(BOLD);
+	assert_eq!(style.__codes, original.__codes);
+	assert!(style.style_eq(&original));
+
+	// Extended color parameters are not attributes
+	let mut style = PrintBuf::new();
+	style.__codes.extend_from_slice(&[38, 5, 4]);
+	style.toggle(UNDERLINE);
+	assert_eq!(style.__codes, [38, 5, 4, 4]);
+	style.toggle(UNDERLINE);
+	assert_eq!(style.__codes, [38, 5, 4, 24]);
+
+	style.toggle(RED);
+	assert_eq!(style.__codes, [38, 5, 4, 24]);
+
+	// Attributes sharing a reset survive the toggle
+	let original = mode!(DIM).to_owned_bytes();
+	let mut style = original.clone();
+	style.toggle(BOLD);
+	assert_eq!(style.__codes, [2, 1]);
+	style.toggle(BOLD);
+	assert_eq!(style.__codes, original.__codes);
+	style.toggle(BOLD);
+	assert!(style.style_eq(&mode!(DIM; BOLD)));
+
+	let mut style = mode!(DIM; RESET_WEIGHT).to_owned_bytes();
+	style.toggle(BOLD);
+	assert_eq!(style.__codes, [2, 22, 1]);
+	style.toggle(BOLD);
+	assert!(style.style_eq(&mode!(RESET_WEIGHT)));
+
+	let mut style = mode!(BLINK).to_owned_bytes();
+	style.toggle(Code { __byte: 6 });
+	style.toggle(Code { __byte: 6 });
+	assert!(style.style_eq(&mode!(BLINK)));
+}
+
+#[test]
+fn pager_safe_codes() {
+	let mut buf = [0u8; 64];
+	assert_eq!(pager_safe(&mode!(BOLD; {Code { __byte: 6 }}; RED; {Code { __byte: 53 }}).__codes, &mut buf), Some("\x1b[1;31m"));
+	assert_eq!(pager_safe(&mode!(RESET; HIDDEN; STRIKE; FG PAL 9; BG RGB 1, 2, 3).__codes, &mut buf), Some("\x1b[0;38;5;9;48;2;1;2;3m"));
+	assert_eq!(pager_safe(&[51], &mut buf), Some(""));
+}
+
+#[test]
+fn color_display() {
+	assert_eq!(format!("{}", Color::Named(RED)), "\x1b[31m");
+	assert_eq!(format!("{}", Color::Named(RED).bg()), "\x1b[41m");
+	assert_eq!(format!("{}", Color::Named(BRIGHT_BLUE_BG)), "\x1b[94m");
+	assert_eq!(format!("{}", Color::Named(BRIGHT_BLUE_BG).bg()), "\x1b[104m");
+	assert_eq!(format!("{}", Color::Named(DEFAULT).bg()), "\x1b[49m");
+	assert_eq!(format!("{}", Color::Palette(9)), format!("{}", mode!(FG PAL 9)));
+	assert_eq!(format!("{}", Color::Palette(9).bg()), format!("{}", mode!(BG PAL 9)));
+	assert_eq!(format!("{}", Color::Rgb(1, 2, 3)), format!("{}", mode!(FG RGB 1, 2, 3)));
+	assert_eq!(format!("{}", Color::Rgb(1, 2, 3).bg()), format!("{}", mode!(BG RGB 1, 2, 3)));
+	let print: Print<ColorCodes> = Color::Rgb(4, 5, 6).into();
+	assert_eq!(print.__codes.as_ref(), [38, 2, 4, 5, 6]);
+}
+
+#[test]
+fn color_conversions() {
+	assert_eq!(Color::from(RED), Color::Named(RED));
+	assert_eq!(Color::from(28u8), Color::Palette(28));
+	assert_eq!(Color::from((1, 2, 3)), Color::Rgb(1, 2, 3));
+	assert_eq!(Color::from([1, 2, 3]), Color::Rgb(1, 2, 3));
+	fn fg(color: impl Into<Color>) -> String {
+		format!("{}", color.into())
+	}
+	assert_eq!(fg(RED), "\x1b[31m");
+	assert_eq!(fg(28), format!("{}", mode!(FG PAL 28)));
+	assert_eq!(fg((243, 159, 24)), format!("{}", mode!(FG RGB 243, 159, 24)));
+	assert_eq!(fg([243, 159, 24]), format!("{}", fg_rgb_arr([243, 159, 24])));
+}
+
+#[test]
+fn palette88() {
+	use palette::rgb_to_palette88;
+	assert_eq!(rgb_to_palette88(0, 0, 0), 16);
+	assert_eq!(rgb_to_palette88(255, 255, 255), 79);
+	assert_eq!(rgb_to_palette88(255, 0, 0), 16 + 48);
+	assert_eq!(rgb_to_palette88(0, 0, 255), 16 + 3);
+	assert_eq!(rgb_to_palette88(139, 139, 139), 16 + 16 + 4 + 1);
+	assert_eq!(rgb_to_palette88(90, 94, 92), 81);
+	assert_eq!(rgb_to_palette88(200, 140, 10), 16 + 32 + 4);
+	for r in (0..=255).step_by(15) {
+		for g in (0..=255).step_by(15) {
+			for b in (0..=255).step_by(15) {
+				assert!((16..88).contains(&rgb_to_palette88(r, g, b)));
+			}
+		}
+	}
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn split_fg_bg() {
+	let (fg, bg) = mode!(BOLD; RED; BLUE_BG).split_fg_bg();
+	assert_eq!(fg.__codes, [1, 31]);
+	assert_eq!(bg.__codes, [44]);
+
+	let (fg, bg) = mode!(BG RGB 38, 5, 1; UNDERLINE; FG PAL 48; DEFAULT_BG).split_fg_bg();
+	assert_eq!(fg.__codes, [4, 38, 5, 48]);
+	assert_eq!(bg.__codes, [48, 2, 38, 5, 1, 49]);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn fg_bg_only() {
+	let style = mode!(BOLD; RED; BLUE_BG);
+	assert_eq!(style.fg_only().__codes, [31]);
+	assert_eq!(style.bg_only().__codes, [44]);
+
+	let style = mode!(BG RGB 38, 5, 1; UNDERLINE; FG PAL 48; RESET; DEFAULT_BG);
+	assert_eq!(style.fg_only().__codes, [38, 5, 48]);
+	assert_eq!(style.bg_only().__codes, [48, 2, 38, 5, 1, 49]);
+	assert!(mode!(BOLD).fg_only().__codes.is_empty());
+}
+
+#[cfg(feature = "termcolor")]
+#[test]
+fn termcolor_spec() {
+	let mut spec = termcolor::ColorSpec::new();
+	spec.set_bold(true).set_fg(Some(termcolor::Color::Red));
+	let style = PrintBuf::from_color_spec(&spec);
+	assert_eq!(style.__codes, [0, 1, 31]);
+	assert_eq!(style.to_color_spec(), spec);
+
+	let spec = mode!(BOLD; RED).to_color_spec();
+	assert!(spec.bold() && !spec.reset());
+	assert_eq!(spec.fg(), Some(&termcolor::Color::Red));
+	assert_eq!(PrintBuf::from_color_spec(&spec).__codes, [1, 31]);
+
+	let spec = mode!(UNDERLINE; BRIGHT_RED; BG RGB 1, 2, 3; RESET_UNDERLINE).to_color_spec();
+	assert!(!spec.underline());
+	assert_eq!(spec.fg(), Some(&termcolor::Color::Ansi256(9)));
+	assert_eq!(spec.bg(), Some(&termcolor::Color::Rgb(1, 2, 3)));
+}
+
+#[test]
+fn truncate_visible_text() {
+	let mut buf = [0u8; 64];
+	let s = "\x1b[1mab\x1b[31mcd\x1b[0mef";
+	assert_eq!(truncate_visible(s, 0, &mut buf), Some("\x1b[1m\x1b[0m"));
+	assert_eq!(truncate_visible(s, 3, &mut buf), Some("\x1b[1mab\x1b[31mc\x1b[0m"));
+	assert_eq!(truncate_visible(s, 4, &mut buf), Some("\x1b[1mab\x1b[31mcd\x1b[0m"));
+	assert_eq!(truncate_visible(s, 5, &mut buf), Some("\x1b[1mab\x1b[31mcd\x1b[0me"));
+	assert_eq!(truncate_visible(s, 6, &mut buf), Some(s));
+	assert_eq!(truncate_visible(s, 100, &mut buf), Some(s));
+
+	// Explicitly turned off styles are not active
+	assert_eq!(truncate_visible("\x1b[4ma\x1b[24mbc", 2, &mut buf), Some("\x1b[4ma\x1b[24mb"));
+	assert_eq!(truncate_visible("\x1b[38;5;1ma\x1b[39mbc", 2, &mut buf), Some("\x1b[38;5;1ma\x1b[39mb"));
+	assert_eq!(truncate_visible("ünïcödé", 3, &mut buf), Some("ünï"));
+	assert_eq!(truncate_visible("ab\x1b[2Jcd", 3, &mut buf), Some("ab\x1b[2Jc"));
+	assert_eq!(truncate_visible("ab\x1b]0;title\x07cd", 2, &mut buf), Some("ab\x1b]0;title\x07"));
+	assert_eq!(truncate_visible("a\x1b[?25", 1, &mut buf), Some("a\x1b[?25"));
+	assert_eq!(truncate_visible("a\x1bébc", 2, &mut buf), Some("a\x1béb"));
+	assert_eq!(truncate_visible("\x1b[31mabc", 2, &mut buf[..8]), None);
+}
+
+#[test]
+fn starts_ends_reset() {
+	assert!(mode!(BOLD; RESET).ends_reset());
+	assert!(mode!(RESET).ends_reset() && mode!(RESET).starts_reset());
+	assert!(!mode!(RESET; BOLD).ends_reset());
+	assert!(!mode!(BG RGB 0, 0, 0).ends_reset());
+	assert!(!mode!().ends_reset() && !mode!().starts_reset());
+	assert!(mode!(RESET; RED).starts_reset());
+	assert!(!mode!(RED; RESET).starts_reset());
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn style_string() {
+	let s = StyleString::new().bold().red().text("hi").reset();
+	assert_eq!(s.as_str(), "\x1b[1m\x1b[31mhi\x1b[0m");
+	let s = StyleString::new().fg(Color::Palette(9)).bg(Color::Named(BLUE)).text("x").style(mode!(BOLD; UNDERLINE)).bright_white_bg();
+	assert_eq!(String::from(s), "\x1b[38;5;9m\x1b[44mx\x1b[1;4m\x1b[107m");
+	assert_eq!(StyleString::default(), StyleString::new());
+	assert_eq!(StyleString::default().red().default_fg().as_str(), "\x1b[31m\x1b[39m");
+}
+
+#[test]
+fn ring_writer() {
+	use core::fmt::Write;
+
+	let mut buf = [0u8; 12];
+	let mut ring = RingWriter::new(&mut buf, Overflow::Error);
+	write!(ring, "{}{}", BOLD, RED).unwrap();
+	assert_eq!(ring.len(), 9);
+	assert!(write!(ring, "{}", RESET).is_err());
+	assert_eq!(ring.as_slices(), (&b"\x1b[1m\x1b[31m"[..], &b""[..]));
+	ring.consume(4);
+	write!(ring, "{}", RESET).unwrap();
+	assert_eq!(ring.as_slices(), (&b"\x1b[31m\x1b[0"[..], &b"m"[..]));
+
+	let mut buf = [0u8; 8];
+	let mut ring = RingWriter::new(&mut buf, Overflow::DropOldest);
+	write!(ring, "{}ab", RED).unwrap();
+	write!(ring, "{}", RESET).unwrap();
+	assert_eq!(ring.len(), 8);
+	let (a, b) = ring.as_slices();
+	assert_eq!([a, b].concat(), b"1mab\x1b[0m");
+	write!(ring, "0123456789").unwrap();
+	let (a, b) = ring.as_slices();
+	assert_eq!([a, b].concat(), b"23456789");
+	ring.clear();
+	assert!(ring.is_empty());
+}
+
+#[test]
+fn sorted_codes() {
+	assert_eq!(mode!(RED; BOLD).sorted().__codes, [1, 31]);
+	assert_eq!(mode!(BG RGB 1, 2, 3; FG PAL 1; UNDERLINE; RED; BOLD).sorted().__codes, [4, 1, 38, 5, 1, 31, 48, 2, 1, 2, 3]);
+	// Resets are barriers
+	assert_eq!(mode!(RED; BOLD; RESET; BLUE_BG; ITALIC; GREEN).sorted().__codes, [1, 31, 0, 3, 32, 44]);
+	// Attribute and reset order is preserved
+	assert_eq!(mode!(GREEN; RESET_WEIGHT; BOLD).sorted().__codes, [22, 1, 32]);
+	// Truncated extended colors stay at the end
+	assert_eq!(Print { __codes: [44, 1, 38, 5] }.sorted().__codes, [1, 44, 38, 5]);
+	assert_eq!(Print { __codes: [44, 38, 2, 1] }.sorted().__codes, [44, 38, 2, 1]);
+	let style = mode!(BLUE_BG; DIM; FG RGB 9, 9, 9);
+	assert!(style.sorted().style_eq(&style));
+}
+
+#[test]
+fn visible_chars() {
+	let s = "\x1b[31m\x1b[1mä\x1b[0mb\x1b[4";
+	let chars: Vec<(usize, char)> = visible_char_indices(s).collect();
+	assert_eq!(chars, [(9, 'ä'), (15, 'b'), (16, '\x1b'), (17, '['), (18, '4')]);
+	for (i, c) in chars {
+		assert!(s[i..].starts_with(c));
+	}
+	assert_eq!(visible_char_indices("\x1b[m").next(), None);
+}
+
+#[test]
+fn packed_rgb() {
+	assert_eq!(rgb_u32(0xFF0000), (255, 0, 0));
+	assert_eq!(rgb_u32(0xAB123456), (0x12, 0x34, 0x56));
+	assert_eq!(format!("{}", mode!(FG HEX 0xFF0000)), "\x1b[38;2;255;0;0m");
+	let packed = 0x00F39F18;
+	assert_eq!(format!("{}", mode!(BOLD; BG HEX packed; FG HEX 0x0000FF)), format!("{}", mode!(BOLD; BG RGB 243, 159, 24; FG RGB 0, 0, 255)));
+
+	// The expression is evaluated once
+	let mut calls = 0;
+	let mut next = || { calls += 1; 0x010203 };
+	assert_eq!(mode!(FG HEX next(); BG HEX next()).__codes, [38, 2, 1, 2, 3, 48, 2, 1, 2, 3]);
+	assert_eq!(calls, 2);
+	const PACKED: Print<[u8; 5]> = mode!(FG HEX 0x0A0B0C);
+	assert_eq!(PACKED.__codes, [38, 2, 10, 11, 12]);
+}
+
+#[test]
+fn fresh_style() {
+	assert_eq!(format!("{}", mode!(BOLD; FG PAL 100).fresh()), "\x1b[0;1;38;5;100m");
+	assert_eq!(format!("{}", mode!(BOLD; RED).fresh()), format!("{}", mode!(RESET; BOLD; RED)));
+	assert_eq!(format!("{}", mode!().fresh()), "\x1b[0m");
+}
+
+#[test]
+fn with_reset_style() {
+	assert_eq!(format!("{}", mode!(BOLD; FG PAL 100).with_reset()), "\x1b[1;38;5;100;0m");
+	assert_eq!(format!("{}", mode!(BOLD; RED).with_reset()), format!("{}", mode!(BOLD; RED; RESET)));
+	assert_eq!(format!("{}", mode!().with_reset()), "\x1b[0m");
+	assert_eq!(mode!(BOLD; FG PAL 100).with_reset().bytes(), [1, 38, 5, 100, 0]);
+	const RESET_RED: Print<ResetAppended> = mode!(RED).with_reset();
+	assert_eq!(RESET_RED.bytes(), [31, 0]);
+	assert!(RESET_RED.ends_reset());
+}
+
+#[test]
+fn count_sgr_sequences() {
+	let s = format!("{}a{}b{}c{}", mode!(BOLD; RED), BLUE_BG, mode!(FG RGB 1, 2, 3), RESET);
+	assert_eq!(count_sgr(&s), 4);
+	assert_eq!(count_sgr("\x1b[1mx\x1b[2Jy\x1b[m\x1b[1;3"), 2);
+	assert_eq!(count_sgr(""), 0);
+}
+
+#[test]
+fn private_mode_toggles() {
+	use private_modes::*;
+	assert_eq!(format!("{}", AlternateScreen(true)), "\x1b[?1049h");
+	assert_eq!(format!("{}", AlternateScreen(false)), "\x1b[?1049l");
+	assert_eq!(format!("{}", CursorBlink(true)), "\x1b[?12h");
+	assert_eq!(format!("{}", BracketedPaste(true)), "\x1b[?2004h");
+	assert_eq!(format!("{}", MouseTracking(false)), "\x1b[?1000l");
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn map_colors_swap() {
+	let swap = |color| match color {
+		Color::Named(RED) => Color::Named(BLUE),
+		Color::Named(BLUE) => Color::Named(RED),
+		Color::Named(BLUE_BG) => Color::Named(RED_BG),
+		color => color,
+	};
+	let style = mode!(BOLD; RED; BLUE_BG; UNDERLINE; FG RGB 1, 2, 3);
+	assert_eq!(style.map_colors(swap).__codes, [1, 34, 41, 4, 38, 2, 1, 2, 3]);
+	let style = mode!(FG PAL 9; BLUE);
+	let mapped = style.map_colors(|color| match color {
+		Color::Palette(9) => Color::Rgb(255, 0, 0),
+		color => swap(color),
+	});
+	assert_eq!(mapped.__codes, [38, 2, 255, 0, 0, 31]);
+	let mut truncated = PrintBuf::new();
+	truncated.__codes.extend_from_slice(&[1, 38, 5]);
+	assert_eq!(truncated.map_colors(swap).__codes, [1, 38, 5]);
+}
+
+#[test]
+fn params_only_codes() {
+	let mut buf = [0u8; 32];
+	assert_eq!(params_only(&mode!(BOLD; RED).__codes, &mut buf), Some("1;31"));
+	assert_eq!(params_only(&mode!(FG PAL 100; BRIGHT_WHITE_BG).__codes, &mut buf), Some("38;5;100;107"));
+	assert_eq!(params_only(&[], &mut buf), Some(""));
+	assert_eq!(params_only(&[1, 31], &mut buf[..3]), None);
+	assert_eq!(params_only(&[1, 31], &mut buf[..4]), Some("1;31"));
+}
+
+#[test]
+fn scoped_reset_codes() {
+	assert_eq!(mode!(RED).scoped_reset().__codes.as_ref(), [39]);
+	assert_eq!(mode!(FG RGB 1, 2, 3).scoped_reset().__codes.as_ref(), [39]);
+	assert_eq!(mode!(BG PAL 28).scoped_reset().__codes.as_ref(), [49]);
+	assert_eq!(mode!(BOLD).scoped_reset().__codes.as_ref(), [22]);
+	assert_eq!(mode!(DIM; BOLD).scoped_reset().__codes.as_ref(), [22]);
+	assert_eq!(mode!(ITALIC).scoped_reset().__codes.as_ref(), [23]);
+	assert_eq!(mode!(UNDERLINE).scoped_reset().__codes.as_ref(), [24]);
+	assert_eq!(mode!(BLINK).scoped_reset().__codes.as_ref(), [25]);
+	assert_eq!(mode!(INVERSE).scoped_reset().__codes.as_ref(), [27]);
+	assert_eq!(mode!(HIDDEN).scoped_reset().__codes.as_ref(), [28]);
+	assert_eq!(mode!(STRIKE).scoped_reset().__codes.as_ref(), [29]);
+	assert_eq!(mode!(STRIKE; BRIGHT_RED_BG; BOLD; BLUE).scoped_reset().__codes.as_ref(), [22, 29, 39, 49]);
+	assert_eq!(mode!(RESET_WEIGHT; DEFAULT).scoped_reset().__codes.as_ref(), []);
+	assert_eq!(Print { __codes: [1u8, 53] }.scoped_reset().__codes.as_ref(), [0]);
+	assert_eq!(Print { __codes: [21u8, 26] }.scoped_reset().__codes.as_ref(), [0]);
+	assert_eq!(format!("{}", Print::from_codes([21, 26]).scoped_reset()), "\x1b[0m");
+}
+
+#[test]
+fn print_bytes() {
+	assert_eq!(mode!(BOLD; RED).bytes(), &[1, 31]);
+	assert_eq!(mode!(BG PAL 28).bytes(), &[48, 5, 28]);
+	assert_eq!(mode!().bytes(), &[]);
+}
+
+#[cfg(feature = "crossterm")]
+#[test]
+fn crossterm_conversions() {
+	use crossterm::style::{Attribute, Color};
+	assert_eq!(Color::try_from(RED), Ok(Color::DarkRed));
+	assert_eq!(Color::try_from(RED_BG), Ok(Color::DarkRed));
+	assert_eq!(Color::try_from(BRIGHT_BLACK), Ok(Color::DarkGrey));
+	assert_eq!(Color::try_from(WHITE), Ok(Color::Grey));
+	assert_eq!(Color::try_from(DEFAULT_BG), Ok(Color::Reset));
+	assert_eq!(Color::try_from(BOLD), Err(UnsupportedCode(BOLD)));
+	assert_eq!(Attribute::try_from(BOLD), Ok(Attribute::Bold));
+	assert_eq!(Attribute::try_from(RESET_WEIGHT), Ok(Attribute::NormalIntensity));
+	assert_eq!(Attribute::try_from(STRIKE), Ok(Attribute::CrossedOut));
+	assert_eq!(Attribute::try_from(RED), Err(UnsupportedCode(RED)));
+}
+
+#[test]
+fn sanitize_text() {
+	let mut buf = [0u8; 64];
+	assert_eq!(sanitize("\x1b]8;;https://evil.example\x1b\\link\x1b]8;;\x1b\\", &mut buf), Some("link"));
+	assert_eq!(sanitize("\x1b]0;title\x07a\x1b[2J\x1b[1;31mb\x1b[?1049hc", &mut buf), Some("abc"));
+	assert_eq!(sanitize("a\x1bPq#0;2;0;0;0\x1b\\b\x1b_apc\x1b\\c", &mut buf), Some("abc"));
+	assert_eq!(sanitize("a\x1b7b\x1b(Bc\x1bcd", &mut buf), Some("abcd"));
+	assert_eq!(sanitize("a\u{9b}31mb\u{85}c", &mut buf), Some("a31mbc"));
+	assert_eq!(sanitize("line\n\tnext", &mut buf), Some("line\n\tnext"));
+	assert_eq!(sanitize("ok\x1b]8;;unterminated", &mut buf), Some("ok"));
+	assert_eq!(sanitize("ok\x1b", &mut buf), Some("ok"));
+	assert_eq!(sanitize("\x1bé!", &mut buf), Some("!"));
+	assert_eq!(sanitize("toolong", &mut buf[..4]), None);
+}
+
+#[test]
+fn when_cond() {
+	assert_eq!(format!("{}", RED.when(true)), "\x1b[31m");
+	assert_eq!(format!("{}", RED.when(false)), "");
+	assert_eq!(format!("{}", RED.when(true).paint("msg")), "\x1b[31mmsg\x1b[0m");
+	assert_eq!(format!("{}", RED.when(false).paint("msg")), "msg");
+	let style = mode!(BOLD; BG PAL 28);
+	assert_eq!(format!("{}", style.when(true).paint(42)), "\x1b[1;48;5;28m42\x1b[0m");
+	assert_eq!(format!("{}", style.when(false).paint(42)), "42");
+	assert_eq!(format!("{:>4}", RED.when(false).paint(42)), "  42");
+}
+
+#[test]
+fn maybe_style() {
+	assert_eq!(format!("{}", Maybe(Some(RED))), "\x1b[31m");
+	assert_eq!(format!("{}", Maybe::<Code>(None)), "");
+	assert_eq!(format!("{}", Maybe(Some(mode!(BOLD; BG PAL 28)))), "\x1b[1;48;5;28m");
+	assert_eq!(format!("{}", Maybe::<Print<&[u8]>>(None)), "");
+	let style: Maybe<_> = BOLD.reset_for().into();
+	assert_eq!(format!("{}", style), "\x1b[22m");
+	assert_eq!(format!("{}", Maybe::from(RESET.reset_for())), "");
+}
+
+#[test]
+fn visible_rchars_reverse() {
+	let s = "\x1b[1mab\x1b[38;5;1mc\x1b[0m\x1b[mdé\x1b[4m";
+	let forward: String = visible_char_indices(s).map(|(_, c)| c).collect();
+	let reverse: String = visible_rchars(s).collect();
+	assert_eq!(forward, "abcdé");
+	assert_eq!(reverse, "édcba");
+	// Not a valid SGR escape sequence so the characters are visible
+	assert_eq!(visible_rchars("\x1b[1x m").collect::<String>(), "m x1[\x1b");
+	assert_eq!(visible_rchars("[1m").collect::<String>(), "m1[");
+	assert_eq!(visible_rchars("").next(), None);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn cow_print() {
+	static CODES: [u8; 4] = [1, 38, 5, 28];
+	let borrowed = CowPrint::from(Print { __codes: &CODES[..] });
+	let owned = CowPrint::from(Print { __codes: &CODES[..] }.to_owned_bytes());
+	assert!(matches!(borrowed, CowPrint::Borrowed(_)));
+	assert!(matches!(owned, CowPrint::Owned(_)));
+	assert_eq!(format!("{}", borrowed), "\x1b[1;38;5;28m");
+	assert_eq!(format!("{}", borrowed), format!("{}", owned));
+	assert_eq!(PrintBuf::from(borrowed).__codes, PrintBuf::from(owned).__codes);
+}
+
+#[test]
+fn display_code_boundary() {
+	for code in 0..=255u8 {
+		for suffix in 0x20..=0x7eu8 {
+			let expected = format!("{}{}", code, suffix as char);
+			let mut buf = [0xffu8; 5];
+			let len = display_code(code, suffix, &mut buf);
+			assert_eq!(&buf[..len], expected.as_bytes(), "code {} suffix {:?}", code, suffix as char);
+			assert!(buf[len..].iter().all(|&byte| byte == 0xff), "code {} wrote past its length", code);
+		}
+	}
+	let mut buf = [0u8; 4];
+	assert_eq!(display_code(107, b'm', &mut buf[..3]), 0);
+	assert_eq!(display_code(1, b'm', &mut buf[..1]), 0);
+	assert_eq!(display_code(255, b';', &mut buf), 4);
+}
+
+#[test]
+fn code_as_str() {
+	const BOLD_STR: &str = BOLD.as_str();
+	assert_eq!(BOLD_STR, "\x1b[1m");
+	for byte in 0..=255u8 {
+		let code = Code { __byte: byte };
+		let mut expected = String::new();
+		write_codes(&[byte], &mut expected).unwrap();
+		assert_eq!(code.as_str(), expected);
+		assert_eq!(format!("{}", code), expected);
+		assert_eq!(format!("{}", Print { __codes: [byte] }), expected);
+	}
+
+	// Display writes the precomputed sequence itself rather than formatting it
+	use core::fmt::Write;
+	struct Ptr(*const u8);
+	impl Write for Ptr {
+		fn write_str(&mut self, s: &str) -> fmt::Result {
+			self.0 = s.as_ptr();
+			Ok(())
+		}
+	}
+	for byte in 0..=255u8 {
+		let code = Code { __byte: byte };
+		let mut ptr = Ptr(core::ptr::null());
+		write!(ptr, "{}", code).unwrap();
+		assert_eq!(ptr.0, code.as_str().as_ptr());
+	}
+}
+
+#[test]
+fn split_sequences_segments() {
+	let mut buf = [0u8; 64];
+	assert_eq!(split_sequences(&mode!(BOLD; RED).__codes, &mut buf), Some("\x1b[1m\x1b[31m"));
+	assert_eq!(split_sequences(&mode!(FG RGB 1, 2, 3; BG PAL 4; RESET).__codes, &mut buf), Some("\x1b[38;2;1;2;3m\x1b[48;5;4m\x1b[0m"));
+	assert_eq!(split_sequences(&[], &mut buf), Some(""));
+	assert_eq!(split_sequences(&mode!(BOLD; RED).__codes, &mut buf[..8]), None);
+}
+
+#[test]
+fn ansi16_palette_values() {
+	let palette = palette::ansi16_palette();
+	assert_eq!(palette[1], (170, 0, 0));
+	assert_eq!(palette[9], (255, 85, 85));
+	for (i, &rgb) in palette.iter().enumerate() {
+		assert!(!palette[..i].contains(&rgb), "duplicate {:?}", rgb);
+		assert_eq!(palette::palette_to_rgb(i as u8), rgb);
+	}
+}
+
+#[test]
+fn palette_names() {
+	assert_eq!(palette::palette_name(0), Some("black"));
+	assert_eq!(palette::palette_name(1), Some("red"));
+	assert_eq!(palette::palette_name(7), Some("white"));
+	assert_eq!(palette::palette_name(9), Some("bright_red"));
+	assert_eq!(palette::palette_name(15), Some("bright_white"));
+	assert_eq!(palette::palette_name(16), None);
+	assert_eq!(palette::palette_name(255), None);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn extended_colors_atomic() {
+	use state::State;
+	// Every extended color must be complete, a malformed one may only be the last segment
+	fn well_formed(codes: &[u8]) -> bool {
+		let mut iter = segments(codes).peekable();
+		while let Some(segment) = iter.next() {
+			let complete = !matches!(segment[0], 38 | 48) || matches!(segment, [_, 5, _] | [_, 2, _, _, _]);
+			if !complete && iter.peek().is_some() {
+				return false;
+			}
+		}
+		true
+	}
+	fn reparse(s: Option<&str>) -> Vec<u8> {
+		let s = s.unwrap();
+		let mut buf = [0u8; 64];
+		if s.is_empty() { Vec::new() } else { parse(s, &mut buf).unwrap().__codes.to_vec() }
+	}
+	let inputs: [&[u8]; 8] = [
+		&[1, 38, 5, 0, 4],
+		&[48, 2, 0, 38, 5, 22, 38, 5, 1, 0, 31],
+		&[0, 38, 2, 1, 2, 3, 22, 48, 5, 49, 1],
+		&[22, 44, 38, 5, 0, 0, 48, 2, 38, 48, 5],
+		&[1, 38, 1, 44, 4],
+		&[48, 7, 1, 31],
+		&[4, 31, 38, 5],
+		&[38],
+	];
+	let mut buf = [0u8; 128];
+	for &input in &inputs {
+		let print = Print { __codes: input };
+		let mut sorted = input.to_vec();
+		sort_codes(&mut sorted);
+		assert_eq!(State::new(&sorted), State::new(input), "{:?}", input);
+		let mut outputs = vec![
+			sorted,
+			reparse(strip_colors(input, &mut buf)),
+			reparse(strip_attributes(input, &mut buf)),
+			reparse(pager_safe(input, &mut buf)),
+			print.map_colors(|color| color).__codes,
+			print.split_fg_bg().0.__codes,
+			print.split_fg_bg().1.__codes,
+		];
+		let mut toggled = PrintBuf::from(CowPrint::Owned(input.to_vec()));
+		toggled.toggle(BOLD);
+		toggled.toggle(UNDERLINE);
+		outputs.push(toggled.__codes);
+		let mut split = Vec::new();
+		let s = split_sequences(input, &mut buf).unwrap();
+		for sequence in s.split_inclusive('m') {
+			split.extend(reparse(Some(sequence)));
+		}
+		assert_eq!(split, input);
+		if well_formed(input) {
+			for output in &outputs {
+				assert!(well_formed(output), "{:?} -> {:?}", input, output);
+			}
+		}
+	}
+}
+
+#[test]
+fn repeat_char() {
+	assert_eq!(format!("{}", repeat(mode!(RED).erase(), '-', 3)), "\x1b[31m---\x1b[0m");
+	assert_eq!(format!("{}", repeat(mode!(BOLD; FG PAL 8).erase(), '─', 2)), "\x1b[1;38;5;8m──\x1b[0m");
+	assert_eq!(format!("{}", repeat(mode!(RED).erase(), '=', 0)), "\x1b[31m\x1b[0m");
+	assert_eq!(format!("{}", repeat(mode!().erase(), '=', 2)), "==");
+}
+
+#[test]
+fn before_graphics_reset() {
+	let mut buf = [0u8; 16];
+	assert_eq!(before_graphics(&mut buf), Some("\x1b\\\x1b[0m"));
+	assert_eq!(before_graphics(&mut buf[..5]), None);
+	assert_eq!(before_graphics(&mut buf[..6]).map(str::len), Some(6));
+}
+
+#[test]
+fn attributes_only() {
+	let attrs = |print: Print<&[u8]>| print.attributes().map(|code| code.__byte).collect::<Vec<_>>();
+	assert_eq!(attrs(mode!(BOLD; RED; UNDERLINE).erase()), [1, 4]);
+	// Extended color bytes are not attributes
+	assert_eq!(attrs(mode!(FG PAL 1; BG RGB 4, 9, 22; ITALIC).erase()), [3]);
+	assert_eq!(attrs(mode!(RESET; RESET_WEIGHT; DEFAULT).erase()), [22]);
+	assert_eq!(attrs(mode!().erase()), []);
+}
+
+#[test]
+fn print_from_codes() {
+	let expected = format!("{}", mode!(BOLD; RED));
+	assert_eq!(format!("{}", Print::from_codes([1u8, 31])), expected);
+	assert_eq!(format!("{}", Print::from(&[1u8, 31][..])), expected);
+	assert_eq!(format!("{}", Print::from(vec![1u8, 31])), expected);
+	const STYLE: Print<[u8; 3]> = Print::from_codes([38, 5, 28]);
+	assert_eq!(STYLE.bytes(), mode!(FG PAL 28).bytes());
+}
+
+#[test]
+fn styled_underlines() {
+	assert_eq!(format!("{}", CURLY_UNDERLINE), "\x1b[4:3m");
+	assert_eq!(format!("{}", DOTTED_UNDERLINE), "\x1b[4:4m");
+	assert_eq!(format!("{}", DASHED_UNDERLINE), "\x1b[4:5m");
+	let s = format!("{}x{}", CURLY_UNDERLINE, RESET_UNDERLINE);
+	assert_eq!(count_sgr(&s), 2);
+	assert_eq!(visible_char_indices(&s).map(|(_, c)| c).collect::<String>(), "x");
+}
+
+#[test]
+fn mode_fresh_macro() {
+	assert_eq!(format!("{}", mode_fresh!(BOLD)), "\x1b[0;1m");
+	assert_eq!(mode_fresh!(BOLD; FG PAL 28; BG HEX 0x010203).__codes, mode!(RESET; BOLD; FG PAL 28; BG HEX 0x010203).__codes);
+	assert_eq!(mode_fresh!().__codes, [0]);
+	assert_eq!(format!("{}", mode_fresh!(RED)), format!("{}", mode!(RED).fresh()));
+}
+
+#[test]
+fn dedup_reset_codes() {
+	let mut buf = [0u8; 32];
+	assert_eq!(dedup_resets(&[0, 0, 31], &mut buf), Some("\x1b[0;31m"));
+	assert_eq!(dedup_resets(&[1, 0, 31], &mut buf), Some("\x1b[0;31m"));
+	assert_eq!(dedup_resets(&[1, 0, 4, 0], &mut buf), Some("\x1b[0m"));
+	assert_eq!(dedup_resets(&[1, 31], &mut buf), Some("\x1b[1;31m"));
+	// Zeros inside extended colors are not resets
+	assert_eq!(dedup_resets(&[0, 1, 38, 2, 0, 0, 0, 48, 5, 0], &mut buf), Some("\x1b[0;1;38;2;0;0;0;48;5;0m"));
+	assert_eq!(dedup_resets(&[], &mut buf), Some(""));
+	assert_eq!(dedup_resets(&[1, 0, 31], &mut buf[..6]), None);
+}
+
+#[test]
+fn describe_style() {
+	assert_eq!(BOLD.name(), Some("BOLD"));
+	assert_eq!(RESET_STRIKE.name(), Some("RESET_STRIKE"));
+	assert_eq!(Code { __byte: 53 }.name(), None);
+	assert_eq!(format!("{}", mode!(BOLD; RED).describe()), "[BOLD, RED]");
+	assert_eq!(format!("{}", mode!(RESET; FG RGB 1, 2, 3; BG PAL 28).describe()), "[RESET, FG RGB 1, 2, 3, BG PAL 28]");
+	assert_eq!(format!("{}", Print::from_codes([53u8, 38, 7, 1]).describe()), "[53, 38;7;1]");
+	assert_eq!(format!("{}", mode!().describe()), "[]");
+}
+
+style!(pub(crate) HEADER = BOLD; UNDERLINE; FG RGB 243, 159, 24);
+style!(EMPTY =);
+
+#[test]
+fn style_consts() {
+	assert_eq!(format!("{}", HEADER), format!("{}", mode!(BOLD; UNDERLINE; FG RGB 243, 159, 24)));
+	assert_eq!(HEADER.__codes.len(), 7);
+	assert_eq!(format!("{}", EMPTY), "");
+}
+
+#[test]
+fn cursor_goto() {
+	use cursor::Goto;
+	assert_eq!(format!("{}", Goto::new(0, 0)), "\x1b[1;1H");
+	assert_eq!(format!("{}", Goto::new(1, 1)), "\x1b[1;1H");
+	assert_eq!(format!("{}", Goto::new(0, 80)), "\x1b[1;80H");
+	assert_eq!(format!("{}", Goto::new(u16::MAX, 7)), "\x1b[65535;7H");
+	assert_eq!(Goto::new(0, 0), Goto::new(1, 1));
+}
+
+#[test]
+fn frame_diff_updates() {
+	use cursor::frame_diff;
+	let mut buf = [0u8; 64];
+	let frame = format!("{}ab{}c\nde\nfg", RED, RESET);
+	assert_eq!(frame_diff(&frame, &frame, &mut buf), Some(""));
+	assert_eq!(frame_diff("", "", &mut buf), Some(""));
+	// Single changed cell
+	assert_eq!(frame_diff("abc\ndef", "abc\ndXf", &mut buf), Some("\x1b[2;2HX"));
+	// Changed style only, the style carries across lines
+	let next = format!("{}ab{}c\nd{}e\nfg", RED, RESET, BOLD);
+	assert_eq!(frame_diff(&frame, &next, &mut buf), Some("\x1b[2;2H\x1b[0;1me\x1b[3;1Hfg\x1b[0m"));
+	// Adjacent cells share a single cursor movement
+	assert_eq!(frame_diff("abcd", "aXYd", &mut buf), Some("\x1b[1;2HXY"));
+	// Same characters in an equivalent style are unchanged
+	let next = format!("{}ab\x1b[39;0mc\nde\nfg", mode!(RED; BOLD; RESET_WEIGHT));
+	assert_eq!(frame_diff(&frame, &next, &mut buf), Some(""));
+	// Longer lines in the next frame
+	assert_eq!(frame_diff("a\nb", "a\nbc", &mut buf), Some("\x1b[2;2Hc"));
+	// Other escape sequences take no cells
+	assert_eq!(frame_diff("abc", "a\x1b[2Kbc\x1b]0;title\x07", &mut buf), Some(""));
+	assert_eq!(frame_diff("abc", "\x1b[?25la\x1b[1;1HbX", &mut buf), Some("\x1b[1;3HX"));
+	assert_eq!(frame_diff("abc\ndef", "abc\ndXf", &mut buf[..6]), None);
+}
+
+#[test]
+fn display_csi_terminator() {
+	let mut buf = [0u8; 16];
+	assert_eq!(display_csi(&[3], b'A', &mut buf), Some("\x1b[3A"));
+	assert_eq!(display_csi(&[2], b'J', &mut buf), Some("\x1b[2J"));
+	assert_eq!(display_csi(&[10, 200], b'H', &mut buf), Some("\x1b[10;200H"));
+	assert_eq!(display_csi(&[], b'K', &mut buf), Some("\x1b[K"));
+	assert_eq!(display_csi(&[1, 31], b'm', &mut buf), Some(&*format!("{}", mode!(BOLD; RED))));
+	assert_eq!(display_csi(&[100], b'A', &mut buf[..5]), None);
+	assert_eq!(display_csi(&[1], 0xff, &mut buf), None);
+}
+
+/// Serializes the tests which change the global color choice.
+static COLOR_CHOICE_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+#[test]
+fn color_choice_gating() {
+	let _guard = COLOR_CHOICE_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+	set_color_choice(ColorChoice::Always);
+	assert_eq!(color_choice(), ColorChoice::Always);
+	assert!(colors_enabled());
+	assert_eq!(format!("{}", RED.gated().paint("x")), "\x1b[31mx\x1b[0m");
+	assert_eq!(format!("{}", mode!(BOLD).gated()), "\x1b[1m");
+
+	set_color_choice(ColorChoice::Never);
+	assert_eq!(color_choice(), ColorChoice::Never);
+	assert!(!colors_enabled());
+	assert_eq!(format!("{}", RED.gated().paint("x")), "x");
+	assert_eq!(format!("{}", mode!(BOLD).gated()), "");
+
+	set_color_choice(ColorChoice::Auto);
+	assert_eq!(color_choice(), ColorChoice::Auto);
+	assert_eq!(colors_enabled(), choice::env_colors_enabled());
+	let expected = if colors_enabled() { "\x1b[31mx\x1b[0m" } else { "x" };
+	assert_eq!(format!("{}", RED.gated().paint("x")), expected);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn stream_colors_enabled() {
+	use std::io::IsTerminal;
+	let _guard = COLOR_CHOICE_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+	// Redirected output never gets colors
+	assert!(!choice::tty_colors_enabled(false));
+	assert_eq!(choice::tty_colors_enabled(true), choice::env_colors_enabled());
+
+	set_color_choice(ColorChoice::Auto);
+	assert_eq!(stdout_colors_enabled(), choice::tty_colors_enabled(std::io::stdout().is_terminal()));
+	assert_eq!(stderr_colors_enabled(), choice::tty_colors_enabled(std::io::stderr().is_terminal()));
+	// Cached result is stable
+	assert_eq!(stdout_colors_enabled(), stdout_colors_enabled());
+
+	set_color_choice(ColorChoice::Always);
+	assert!(stdout_colors_enabled() && stderr_colors_enabled());
+	set_color_choice(ColorChoice::Never);
+	assert!(!stdout_colors_enabled() && !stderr_colors_enabled());
+	set_color_choice(ColorChoice::Auto);
+}
+
+#[test]
+fn apply_all_items() {
+	let s = format!("{}", apply_all(mode!(BOLD; RED).erase(), &["a", "b", "c"]));
+	assert_eq!(s, "\x1b[1;31mabc\x1b[0m");
+	assert_eq!(count_sgr(&s), 2);
+	assert!(s.starts_with("\x1b[1;31ma") && s.ends_with("c\x1b[0m"));
+	assert_eq!(format!("{}", apply_all::<u8>(mode!(RED).erase(), &[])), "\x1b[31m\x1b[0m");
+	assert_eq!(format!("{}", apply_all(mode!().erase(), &[1, 2])), "12");
+}
+
+#[test]
+fn brighten_colors() {
+	assert_eq!(GREEN.brighten(), Some(BRIGHT_GREEN));
+	assert_eq!(BLACK.brighten(), Some(BRIGHT_BLACK));
+	assert_eq!(WHITE_BG.brighten(), Some(BRIGHT_WHITE_BG));
+	assert_eq!(BOLD.brighten(), None);
+	assert_eq!(DEFAULT.brighten(), None);
+	assert_eq!(DEFAULT_BG.brighten(), None);
+	assert_eq!(BRIGHT_RED.brighten(), None);
+	assert_eq!(Code { __byte: 38 }.brighten(), None);
+}
+
+#[test]
+fn progress_bar() {
+	let filled = mode!(GREEN_BG);
+	let empty = mode!(BRIGHT_BLACK_BG);
+	let render = |width, ratio| format!("{}", bar(filled.erase(), empty.erase(), width, ratio));
+	assert_eq!(render(10, 0.5), "\x1b[42m     \x1b[0;100m     \x1b[0m");
+	assert_eq!(render(4, 1.0), "\x1b[42m    \x1b[0m");
+	assert_eq!(render(4, 0.0), "\x1b[100m    \x1b[0m");
+	assert_eq!(render(4, 2.0), render(4, 1.0));
+	assert_eq!(render(4, -1.0), render(4, 0.0));
+	assert_eq!(render(4, f32::NAN), render(4, 0.0));
+	assert_eq!(render(3, 0.5), "\x1b[42m  \x1b[0;100m \x1b[0m");
+	assert_eq!(render(0, 0.5), "\x1b[0m");
+}
+
+#[test]
+fn parse_partial_stream() {
+	let mut buf = [0u8; 16];
+	match parse_partial("\x1b[1;38;5;28mtext\x1b[0m", &mut buf) {
+		Some(ParseResult::Complete(print, consumed)) => {
+			assert_eq!(print.__codes, [1, 38, 5, 28]);
+			assert_eq!(consumed, 12);
+		},
+		_ => panic!("expected a complete sequence"),
+	}
+	for s in ["\x1b", "\x1b[", "\x1b[1;3", "\x1b[;"] {
+		assert!(matches!(parse_partial(s, &mut buf), Some(ParseResult::Incomplete)), "{:?}", s);
+	}
+	for s in ["", "text", "\x1b]", "\x1b[1x", "\x1b[300m"] {
+		assert!(parse_partial(s, &mut buf).is_none(), "{:?}", s);
+	}
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn print_buf_with_capacity() {
+	let mut style = PrintBuf::with_capacity(4);
+	assert!(style.capacity() >= 4);
+	let ptr = style.__codes.as_ptr();
+	style.push(BOLD);
+	style.push(UNDERLINE);
+	style.push(RED);
+	style.push(BLUE_BG);
+	assert_eq!(style.__codes.as_ptr(), ptr);
+	assert_eq!(format!("{}", style), "\x1b[1;4;31;44m");
+	assert_eq!(PrintBuf::with_capacity(0).capacity(), 0);
+}
+
+#[test]
+fn coalesce_runs() {
+	let mut buf = [0u8; 64];
+	assert_eq!(coalesce("\x1b[31ma\x1b[31mb", &mut buf), Some("\x1b[31mab"));
+	assert_eq!(coalesce("\x1b[31ma\x1b[32mb\x1b[32mc", &mut buf), Some("\x1b[31ma\x1b[32mbc"));
+	assert_eq!(coalesce("\x1b[1;31ma\x1b[31mb\x1b[0mc\x1b[0m", &mut buf), Some("\x1b[1;31mab\x1b[0mc"));
+	// A reset after other codes is not redundant
+	assert_eq!(coalesce("\x1b[0ma\x1b[1mb\x1b[0mc", &mut buf), Some("\x1b[0ma\x1b[1mb\x1b[0mc"));
+	// Uninterpretable and other escape sequences are kept
+	assert_eq!(coalesce("\x1b[4:3ma\x1b[4:3mb\x1b[2Jc", &mut buf), Some("\x1b[4:3ma\x1b[4:3mb\x1b[2Jc"));
+	assert_eq!(coalesce("\x1b[38;5;1ma\x1b[38;5;1m", &mut buf), Some("\x1b[38;5;1ma"));
+	assert_eq!(coalesce("\x1b[0;21ma\x1b[24mb", &mut buf), Some("\x1b[0;21ma\x1b[24mb"));
+	assert_eq!(coalesce("\x1b[53ma\x1b[55mb\x1b[55mc", &mut buf), Some("\x1b[53ma\x1b[55mb\x1b[55mc"));
+	assert_eq!(coalesce("plain", &mut buf), Some("plain"));
+	assert_eq!(coalesce("\x1b[31ma\x1b[31mb", &mut buf[..6]), None);
+}
+
+#[test]
+fn strip_writer() {
+	use core::fmt::Write;
+	let mut writer = StripWriter::new(String::new());
+	write!(writer, "{}bold{} {}rgb{}", mode!(BOLD; UNDERLINE), RESET, mode!(FG RGB 243, 159, 24), RESET).unwrap();
+	assert_eq!(writer.get_ref(), "bold rgb");
+	// Sequences split across writes
+	for part in ["a\x1b", "[3", "1", "mb\x1b", "[", "0mc"] {
+		writer.write_str(part).unwrap();
+	}
+	assert_eq!(writer.get_ref(), "bold rgbabc");
+	// Other escape sequences are kept
+	writer.write_str("\x1b[2J\x1b\x1b]0;t\x07é").unwrap();
+	assert_eq!(writer.get_ref(), "bold rgbabc\x1b[2J\x1b\x1b]0;t\x07é");
+	writer.write_str("!\x1b[1;").unwrap();
+	assert_eq!(writer.into_inner().unwrap(), "bold rgbabc\x1b[2J\x1b\x1b]0;t\x07é!\x1b[1;");
+}
+
+#[test]
+fn private_csi_sequences() {
+	use core::fmt::Write;
+	assert_eq!(csi_sequence_len("\x1b[?25h"), Some(6));
+	assert_eq!(csi_sequence_len("\x1b[=5u!"), Some(5));
+	assert_eq!(csi_sequence_len("\x1b[?5$p"), Some(6));
+	assert_eq!(csi_sequence_len("\x1b[1;31m"), Some(7));
+	assert_eq!(csi_sequence_len("\x1b[1?h"), None);
+	assert_eq!(csi_sequence_len("\x1b[?25"), None);
+	assert_eq!(sgr_sequence_len("\x1b[>4;1m"), None);
+	assert_eq!(count_sgr("\x1b[>4;1m"), 0);
+
+	let mut writer = StripWriter::new(String::new());
+	write!(writer, "a\x1b[?25hb{}\x1b[>4;1mc\x1b[=1;2ud", private_modes::CursorBlink(false)).unwrap();
+	for part in ["e\x1b", "[", "?", "2004", "h", "f"] {
+		writer.write_str(part).unwrap();
+	}
+	assert_eq!(writer.into_inner().unwrap(), "abcdef");
+
+	let mut buf = [0u8; 32];
+	assert_eq!(sanitize("a\x1b[?25hb\x1b[>4;1mc\x1b[=1ud", &mut buf), Some("abcd"));
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn encode_image_half_blocks() {
+	let (r, g, b, w) = ((255, 0, 0), (0, 255, 0), (0, 0, 255), (255, 255, 255));
+	let s = encode_image(&[r, g, b, w], 2);
+	assert_eq!(s, "\x1b[38;2;255;0;0;48;2;0;0;255m▀\x1b[38;2;0;255;0;48;2;255;255;255m▀\x1b[0m\n");
+	// Odd height
+	let s = encode_image(&[r, g, b], 1);
+	assert_eq!(s, "\x1b[38;2;255;0;0;48;2;0;255;0m▀\x1b[0m\n\x1b[38;2;0;0;255;49m▀\x1b[0m\n");
+	assert_eq!(encode_image(&[], 4), "");
+	assert_eq!(encode_image(&[r], 0), "");
+}
+
+#[test]
+fn eq_ignoring_reset_codes() {
+	assert!(Print::from_codes([31u8, 0]).eq_ignoring_resets(&mode!(RED)));
+	assert!(mode!(RESET; BOLD; RESET; RED).eq_ignoring_resets(&mode!(BOLD; RED)));
+	assert!(!mode!(BOLD; RED).eq_ignoring_resets(&mode!(RED; BOLD)));
+	// Zeros inside extended colors are not resets
+	assert!(!mode!(FG RGB 0, 0, 0).eq_ignoring_resets(&Print::from_codes([38u8, 2])));
+	assert!(mode!(RESET).eq_ignoring_resets(&mode!()));
+}
+
+#[test]
+fn caret_notation() {
+	assert_eq!(format!("{}", BOLD.debug_caret()), "^[[1m");
+	assert_eq!(format!("{}", mode!(BOLD; FG PAL 100).debug_caret()), "^[[1;38;5;100m");
+	assert_eq!(format!("{}", mode!().debug_caret()), "");
+	assert_eq!(format!("{:?}", BOLD), r#""\x1b[1m""#);
+}
+
+#[cfg(feature = "heapless")]
+#[test]
+fn heapless_print_vec() {
+	let mut style = PrintVec::<2>::new();
+	assert_eq!(style.push(BOLD), Ok(()));
+	assert_eq!(style.push(RED), Ok(()));
+	assert_eq!(style.push(UNDERLINE), Err(UNDERLINE));
+	assert_eq!(format!("{}", style), "\x1b[1;31m");
+	assert_eq!(style.as_print().__codes, [1, 31]);
+	assert_eq!(format!("{}", PrintVec::<0>::new()), "");
+}
+
+#[test]
+fn reset_sequences() {
+	assert_eq!(reset_sequence(), "\x1b[0m");
+	assert_eq!(reset_sequence(), format!("{}", RESET));
+	assert_eq!(reset_sequence_full(), "\x1b[0;59m");
+	assert_eq!(count_sgr(reset_sequence_full()), 1);
+}
+
+#[test]
+fn subset_of_styles() {
+	assert!(mode!(BOLD).is_subset_of(&mode!(BOLD; RED)));
+	assert!(mode!(RED; BOLD).is_subset_of(&mode!(BOLD; RED)));
+	assert!(!mode!(BOLD; RED).is_subset_of(&mode!(BOLD)));
+	assert!(!mode!(RED).is_subset_of(&mode!(RED; GREEN)));
+	assert!(mode!(GREEN).is_subset_of(&mode!(RED; GREEN)));
+	assert!(mode!(FG PAL 28).is_subset_of(&mode!(UNDERLINE; FG PAL 28)));
+	assert!(!mode!(FG PAL 28).is_subset_of(&mode!(FG PAL 29)));
+	assert!(!mode!(BOLD).is_subset_of(&mode!(BOLD; RESET_WEIGHT)));
+	assert!(mode!().is_subset_of(&mode!()));
+	assert!(!mode!(RESET).is_subset_of(&mode!(BOLD)));
+	assert!(mode!(RESET).is_subset_of(&mode!(RESET)));
+}
+
+#[test]
+fn sgr_param_limit() {
+	let sequence = |params: usize| format!("\x1b[{}m", vec!["1"; params].join(";"));
+	let mut buf = [0u8; MAX_SGR_PARAMS + 1];
+	let longest = sequence(MAX_SGR_PARAMS);
+	assert_eq!(sgr_sequence_len(&longest), Some(longest.len()));
+	assert_eq!(parse(&longest, &mut buf).map(|print| print.__codes.len()), Some(MAX_SGR_PARAMS));
+	let too_long = sequence(MAX_SGR_PARAMS + 1);
+	assert_eq!(sgr_sequence_len(&too_long), None);
+	assert!(parse(&too_long, &mut buf).is_none());
+	assert_eq!(count_sgr(&too_long), 0);
+	assert!(parse_partial(&too_long, &mut buf).is_none());
+	assert!(parse_partial(&too_long[..too_long.len() - 1], &mut buf).is_none());
+	assert_eq!(visible_rchars(&too_long).count(), too_long.len());
+}
+
+#[test]
+fn zebra_rows() {
+	let even = mode!(BLACK_BG);
+	let odd = mode!(BRIGHT_BLACK_BG);
+	let s = format!("{}", zebra(&["one", "two", "three"], even.erase(), odd.erase()));
+	let mut lines = s.lines();
+	assert_eq!(lines.next(), Some("\x1b[40mone\x1b[0m"));
+	assert_eq!(lines.next(), Some("\x1b[100mtwo\x1b[0m"));
+	assert_eq!(lines.next(), Some("\x1b[40mthree\x1b[0m"));
+	assert_eq!(lines.next(), None);
+	assert_eq!(format!("{}", zebra(&["a", "b"], mode!().erase(), odd.erase())), "a\n\x1b[100mb\x1b[0m\n");
+	assert_eq!(format!("{}", zebra(&[], even.erase(), odd.erase())), "");
+}
+
+#[test]
+fn scope_reset() {
+	use core::fmt::Write;
+	let mut s = String::new();
+	scope(&mut s, mode!(RED).erase(), |_| Ok(())).unwrap();
+	assert_eq!(s, "\x1b[31m\x1b[0m");
+	s.clear();
+	scope(&mut s, mode!(BOLD; GREEN).erase(), |s| s.write_str("ok")).unwrap();
+	assert_eq!(s, "\x1b[1;32mok\x1b[0m");
+	s.clear();
+	assert!(scope(&mut s, mode!(RED).erase(), |_| Err(fmt::Error)).is_err());
+	assert_eq!(s, "\x1b[31m");
+}
+
+#[test]
+fn rgb_arrays() {
+	assert_eq!(format!("{}", fg_rgb_arr([255, 0, 0])), format!("{}", mode!(FG RGB 255, 0, 0)));
+	assert_eq!(format!("{}", bg_rgb_arr([1, 2, 3])), format!("{}", mode!(BG RGB 1, 2, 3)));
+	let rgb = [243, 159, 24];
+	assert_eq!(mode!(BOLD; FG ARR rgb; BG ARR [0, 0, 0]).__codes, [1, 38, 2, 243, 159, 24, 48, 2, 0, 0, 0]);
+	assert_eq!(mode!(FG ARR rgb).__codes, fg_rgb_arr(rgb).__codes);
+
+	// The expression is evaluated once
+	let mut calls = 0;
+	let mut next = || { calls += 1; [1, 2, 3] };
+	assert_eq!(mode!(FG ARR next(); BG ARR next()).__codes, [38, 2, 1, 2, 3, 48, 2, 1, 2, 3]);
+	assert_eq!(calls, 2);
+}
+
+#[test]
+fn iter_named_codes() {
+	assert!(iter_named().any(|named| named == ("RED", RED)));
+	assert!(iter_named().any(|named| named == ("BRIGHT_WHITE_BG", BRIGHT_WHITE_BG)));
+	assert_eq!(iter_named().len(), 50);
+	assert_eq!(iter_named().count(), all_codes().len());
+	assert!(iter_named().all(|(name, code)| code.name() == Some(name)));
+}
+
+#[test]
+fn all_codes_consistent() {
+	let codes = all_codes();
+	for (i, code) in codes.iter().enumerate() {
+		assert!(!codes[..i].contains(code), "duplicate {:?}", code);
+		assert!(code.name().is_some(), "{:?} has no name", code);
+		let is_reset = code.name().is_some_and(|name| name.starts_with("RESET") || name.starts_with("DEFAULT"));
+		assert_eq!(code.reset_for().is_some(), !is_reset, "{:?} reset mapping", code);
+		if let Some(reset) = code.reset_for() {
+			assert!(codes.contains(&reset), "{:?} resets to unknown {:?}", code, reset);
+		}
+		if matches!(code.__byte, 30..=37 | 40..=47) {
+			assert!(code.brighten().is_some_and(|bright| codes.contains(&bright)), "{:?} has no bright variant", code);
+		}
+	}
+	for byte in 0..=255u8 {
+		let code = Code { __byte: byte };
+		assert_eq!(code.name().is_some(), codes.contains(&code), "{:?}", code);
+	}
+}
+
+#[test]
+fn code_count() {
+	style!(WARNING = BOLD; FG PAL 214; BG ARR [0, 0, 0]);
+	const _: () = assert!(<Print<[u8; 9]>>::CODE_COUNT == WARNING.__codes.len());
+	fn count<const M: usize>(_: &Print<[u8; M]>) -> usize {
+		Print::<[u8; M]>::CODE_COUNT
+	}
+	assert_eq!(count(&WARNING), 9);
+	assert_eq!(count(&mode!(BOLD; RED)), 2);
+	assert_eq!(count(&mode!()), 0);
+}
+
+/// Deterministic pseudo-random generator for the fuzz tests.
+struct Rng(u64);
+
+impl Rng {
+	fn next(&mut self) -> u64 {
+		// xorshift64
+		self.0 ^= self.0 << 13;
+		self.0 ^= self.0 >> 7;
+		self.0 ^= self.0 << 17;
+		self.0
+	}
+	fn below(&mut self, n: usize) -> usize {
+		(self.next() % n as u64) as usize
+	}
+}
+
+#[test]
+fn fuzz_parse_round_trip() {
+	let mut rng = Rng(0x9E3779B97F4A7C15);
+	let mut buf = [0u8; 64];
+	for _ in 0..10000 {
+		let codes: Vec<u8> = (0..1 + rng.below(32)).map(|_| rng.next() as u8).collect();
+		let s = format!("{}", Print { __codes: &codes[..] });
+		let print = parse(&s, &mut buf).unwrap();
+		assert_eq!(print.__codes, &codes[..]);
+		assert_eq!(format!("{}", print), s);
+		assert!(matches!(parse_partial(&s, &mut buf), Some(ParseResult::Complete(_, len)) if len == s.len()));
+	}
+}
+
+#[test]
+fn fuzz_parse_arbitrary() {
+	const PIECES: [&str; 12] = ["\x1b", "[", "m", ";", ":", "0", "9", "255", "256", "99999999999", "x", "é"];
+	let mut rng = Rng(0x2545F4914F6CDD1D);
+	let mut buf = [0u8; 16];
+	for _ in 0..10000 {
+		let s: String = (0..rng.below(24)).map(|_| PIECES[rng.below(PIECES.len())]).collect();
+		if let Some(print) = parse(&s, &mut buf) {
+			// Display is canonical, parsing it again yields the same codes and the same string
+			let canonical = format!("{}", print);
+			let codes = print.__codes.to_vec();
+			let again = parse(&canonical, &mut buf).unwrap();
+			assert_eq!(again.__codes, &codes[..]);
+			assert_eq!(format!("{}", again), canonical);
+		}
+		let _ = parse_partial(&s, &mut buf);
+		let _ = parse_sgr(&s).map(Iterator::count);
+		let _ = sgr_sequence_len(&s);
+		let _ = count_sgr(&s);
+		assert_eq!(visible_rchars(&s).count(), visible_char_indices(&s).count());
+	}
+}
+
+#[test]
+fn ascii_fallback_markers() {
+	let mut buf = [0u8; 32];
+	assert_eq!(ascii_fallback(mode!(BOLD).erase(), "text", &mut buf), Some("*text*"));
+	assert_eq!(ascii_fallback(mode!(UNDERLINE).erase(), "text", &mut buf), Some("_text_"));
+	assert_eq!(ascii_fallback(mode!(STRIKE; ITALIC; BOLD; FG RGB 1, 2, 3).erase(), "x", &mut buf), Some("*/~x~/*"));
+	assert_eq!(ascii_fallback(mode!(BOLD; RESET_WEIGHT; RED_BG; INVERSE).erase(), "x", &mut buf), Some("x"));
+	assert_eq!(ascii_fallback(mode!().erase(), "plain", &mut buf), Some("plain"));
+	assert_eq!(ascii_fallback(mode!(BOLD).erase(), "text", &mut buf[..5]), None);
+}
+
+#[test]
+fn events_tokenize() {
+	#[derive(Debug, PartialEq)]
+	enum Owned<'a> {
+		Style(Vec<u8>),
+		Text(&'a str),
+		Other(&'a str),
+	}
+	fn tokenize<'a>(s: &'a str, buf: &'a mut [u8]) -> Vec<Owned<'a>> {
+		events(s, buf).map(|event| match event {
+			Event::Style(print) => Owned::Style(print.bytes().to_vec()),
+			Event::Text(text) => Owned::Text(text),
+			Event::Other(sequence) => Owned::Other(sequence),
+		}).collect()
+	}
+	let mut buf = [0u8; 64];
+	let s = format!("{}red{} \x1b[2;5Hbold\x1b[38;1m{}\x1b", RED, mode!(BOLD; FG PAL 28), RESET);
+	assert_eq!(tokenize(&s, &mut buf), [
+		Owned::Style(vec![31]), Owned::Text("red"), Owned::Style(vec![1, 38, 5, 28]), Owned::Text(" "),
+		Owned::Other("\x1b[2;5H"), Owned::Text("bold"), Owned::Style(vec![38, 1]), Owned::Style(vec![0]),
+		Owned::Other("\x1b"),
+	]);
+
+	assert_eq!(tokenize("\x1b[1:2mé\x1b\x1bé", &mut buf), [Owned::Other("\x1b[1:2m"), Owned::Text("é"), Owned::Other("\x1b"), Owned::Other("\x1bé")]);
+	// A second escape byte starts a new sequence
+	assert_eq!(tokenize("\x1b\x1b[31mx\x1b[1;\x1b[mé\x1b]0;t\x1b(B", &mut buf), [
+		Owned::Other("\x1b"), Owned::Style(vec![31]), Owned::Text("x"),
+		Owned::Other("\x1b[1;"), Owned::Style(vec![0]), Owned::Text("é"),
+		Owned::Other("\x1b]0;t"), Owned::Other("\x1b(B"),
+	]);
+	assert!(events("", &mut []).next().is_none());
+
+	// Styles which no longer fit the buffer
+	let mut buf = [0u8; 3];
+	let mut iter = events("\x1b[1;2m\x1b[3;4m", &mut buf);
+	assert!(matches!(iter.next(), Some(Event::Style(print)) if print.bytes() == [1, 2]));
+	assert!(matches!(iter.next(), Some(Event::Other("\x1b[3;4m"))));
+}
+
+#[test]
+fn conflicting_styles() {
+	assert!(mode!(RED).conflicts_with(&mode!(GREEN)));
+	assert!(mode!(FG PAL 28).conflicts_with(&mode!(FG RGB 1, 2, 3)));
+	assert!(mode!(BLUE_BG).conflicts_with(&mode!(DEFAULT_BG)));
+	assert!(mode!(BOLD).conflicts_with(&mode!(RESET_WEIGHT)));
+	assert!(mode!(RESET_UNDERLINE).conflicts_with(&mode!(UNDERLINE)));
+	assert!(mode!(BOLD; RED).conflicts_with(&mode!(RESET)));
+	assert!(!mode!(RED).conflicts_with(&mode!(BOLD)));
+	assert!(!mode!(BOLD).conflicts_with(&mode!(RED; UNDERLINE; BLUE_BG)));
+	assert!(!mode!(RED; BOLD).conflicts_with(&mode!(RED)));
+	assert!(!mode!(RED).conflicts_with(&mode!(GREEN; RED)));
+	assert!(!mode!().conflicts_with(&mode!(RESET)));
+}
+
+#[test]
+fn line_reset_writer() {
+	use core::fmt::Write;
+	let mut writer = LineResetWriter::new(String::new());
+	write!(writer, "a\n{}b\nc\n", mode!(BOLD; RED)).unwrap();
+	assert_eq!(writer.get_ref(), "a\n\x1b[1;31mb\x1b[0m\nc\n");
+	let mut writer = LineResetWriter::new(String::new());
+	// Sequences split across writes and styles turned off again
+	for part in ["\x1b", "[3", "1mx\n", "\x1b[31m\x1b[39my\n", "\x1b[4m", "\x1b[24m\n", "\x1b[1m\x1b[0m\n"] {
+		writer.write_str(part).unwrap();
+	}
+	assert_eq!(writer.into_inner(), "\x1b[31mx\x1b[0m\n\x1b[31m\x1b[39my\n\x1b[4m\x1b[24m\n\x1b[1m\x1b[0m\n");
+	// Malformed sequences are forwarded and do not change the style
+	let mut writer = LineResetWriter::new(String::new());
+	write!(writer, "\x1b[1\n\x1b\x1b[2m\n").unwrap();
+	assert_eq!(writer.into_inner(), "\x1b[1\n\x1b\x1b[2m\x1b[0m\n");
+}
+
+#[test]
+fn palette_ramp_distinct() {
+	let mut buf = [0u8; 256];
+	for count in [0, 1, 2, 5, 12, 100, 216] {
+		let ramp = palette::palette_ramp(count, &mut buf);
+		assert_eq!(ramp.len(), count);
+		assert!(ramp.iter().all(|&index| palette::palette_kind(index) == palette::PaletteKind::Cube));
+		let mut seen = [false; 256];
+		for &index in ramp {
+			assert!(!seen[index as usize], "duplicate {} for count {}", index, count);
+			seen[index as usize] = true;
+		}
+	}
+	assert_eq!(palette::palette_ramp(300, &mut buf).len(), 216);
+	assert_eq!(palette::palette_ramp(3, &mut buf[..2]).len(), 2);
+	assert_eq!(palette::palette_ramp(2, &mut buf), [196, 51]);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn html_doc() {
+	let mut html = String::new();
+	to_html_doc(&format!("{}red{}green{} plain", RED, GREEN, RESET), &mut html);
+	assert_eq!(html, "<span style=\"color:#aa0000\">red</span><span style=\"color:#00aa00\">green</span> plain");
+	assert_eq!(html.matches("<span").count(), html.matches("</span>").count());
+
+	let mut html = String::new();
+	to_html_doc(&format!("{}a{}b{}\x1b[2Jc{}&", mode!(BG RGB 1, 2, 3; UNDERLINE; STRIKE), RED, RED, mode!(INVERSE; RESET_UNDERLINE)), &mut html);
+	assert_eq!(html, concat!(
+		"<span style=\"background-color:#010203;text-decoration:underline line-through\">a</span>",
+		"<span style=\"color:#aa0000;background-color:#010203;text-decoration:underline line-through\">bc</span>",
+		"<span style=\"color:#010203;background-color:#aa0000;text-decoration:line-through\">&amp;</span>",
+	));
+
+	let mut html = String::new();
+	to_html_doc("\x1b[39m<\"plain'>\x1b[0m", &mut html);
+	assert_eq!(html, "&lt;&quot;plain&#39;&gt;");
+
+	let mut html = String::new();
+	to_html_doc("\x1b\x1b[31mx", &mut html);
+	assert_eq!(html, "<span style=\"color:#aa0000\">x</span>");
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn compose_styles() {
+	let base = mode!(RED; BOLD);
+	assert_eq!(PrintBuf::compose(&base, &mode!(BLUE)).__codes, [1, 34]);
+	assert_eq!(PrintBuf::compose(&base, &mode!(UNDERLINE; BG PAL 28)).__codes, [1, 4, 31, 48, 5, 28]);
+	assert_eq!(PrintBuf::compose(&base, &mode!(RESET_WEIGHT)).__codes, [22, 31]);
+	assert_eq!(PrintBuf::compose(&base, &mode!(RESET; GREEN)).__codes, [0, 32]);
+	assert_eq!(PrintBuf::compose(&base, &mode!()).__codes, [1, 31]);
+	assert_eq!(PrintBuf::compose(&mode!(), &mode!()).__codes, []);
+	assert_eq!(PrintBuf::compose(&mode!(BOLD), &mode!(RESET_WEIGHT; DIM)).__codes, [22, 2]);
+	for (base, over) in [(mode!(BOLD; RED; ITALIC).erase(), mode!(RESET_ITALIC; FG RGB 1, 2, 3).erase()), (mode!(DIM).erase(), mode!(BOLD).erase()), (mode!(BOLD).erase(), mode!(RESET_WEIGHT; DIM).erase())] {
+		let composed = PrintBuf::compose(&base, &over);
+		let mut layered = base.to_owned_bytes();
+		layered.__codes.extend_from_slice(over.__codes);
+		assert!(composed.style_eq(&layered), "{:?} {:?}", base, over);
+	}
+}
+
+#[test]
+fn sgr_parser_streaming() {
+	#[derive(Debug, PartialEq)]
+	enum Owned {
+		Style(Vec<u8>),
+		Text(Vec<u8>),
+		Other(Vec<u8>),
+	}
+	fn owned(event: ParsedEvent) -> Owned {
+		match event {
+			ParsedEvent::Style(print) => Owned::Style(print.__codes.to_vec()),
+			ParsedEvent::Text(text) => Owned::Text(text.to_vec()),
+			ParsedEvent::Other(bytes) => Owned::Other(bytes.to_vec()),
+		}
+	}
+	let input = format!("ab{}b\x1b[2Jc\x1b\x1b[0mé\x1b[1:2mxy\x1b[", mode!(BOLD; FG RGB 243, 159, 24));
+	let mut parser = SgrParser::new();
+	let mut events = Vec::new();
+	for &byte in input.as_bytes() {
+		events.extend(parser.feed(byte).map(owned));
+	}
+	events.extend(parser.finish().map(owned));
+	assert!(parser.finish().is_none());
+	let mut buf = [0u8; 16];
+	let whole = parse(&format!("{}", mode!(BOLD; FG RGB 243, 159, 24)), &mut buf).unwrap().__codes.to_vec();
+	assert_eq!(events, [
+		Owned::Text(b"ab".to_vec()),
+		Owned::Style(whole),
+		Owned::Text(b"b".to_vec()),
+		Owned::Other(b"\x1b[2J".to_vec()),
+		Owned::Text(b"c".to_vec()),
+		Owned::Other(b"\x1b".to_vec()),
+		Owned::Style(vec![0]),
+		Owned::Text("é".as_bytes().to_vec()),
+		Owned::Other(b"\x1b[1:2m".to_vec()),
+		Owned::Text(b"xy".to_vec()),
+		Owned::Other(b"\x1b[".to_vec()),
+	]);
+
+	// Long runs of text are split without splitting characters
+	let text = format!("{}{}", "a".repeat(63), "é".repeat(40));
+	let mut parser = SgrParser::new();
+	let mut runs = Vec::new();
+	for &byte in text.as_bytes() {
+		if let Some(ParsedEvent::Text(run)) = parser.feed(byte) {
+			runs.push(String::from_utf8(run.to_vec()).unwrap());
+		}
+	}
+	if let Some(ParsedEvent::Text(run)) = parser.finish() {
+		runs.push(String::from_utf8(run.to_vec()).unwrap());
+	}
+	assert_eq!(runs.len(), 3);
+	assert_eq!(runs.concat(), text);
+
+	// Overlong sequences are not buffered indefinitely
+	let mut parser = SgrParser::new();
+	let long = format!("\x1b[{}m", "1;".repeat(40));
+	let others = long.bytes().filter(|&byte| matches!(parser.feed(byte), Some(ParsedEvent::Other(_)))).count();
+	assert_eq!(others, 1);
+}
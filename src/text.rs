@@ -0,0 +1,819 @@
+use core::fmt::{self, Write};
+use super::{segments, BufWriter, Params, Print};
+use super::state::State;
+
+/// Maximum number of parameters in an SGR escape sequence.
+///
+/// Longer parameter lists are not recognized as SGR escape sequences, this bounds the work done on untrusted input.
+/// The limit is larger than the 255 codes a single `mode!` can produce.
+pub const MAX_SGR_PARAMS: usize = 256;
+
+/// Returns the length of the SGR escape sequence at the start of the string.
+///
+/// An SGR escape sequence starts with `ESC [`, followed by parameters separated by `;` or `:` and terminated by `m`.
+/// Returns `None` if the string does not start with a complete SGR escape sequence or it has more than [`MAX_SGR_PARAMS`] parameters.
+/// CSI sequences with a private parameter prefix (eg. `ESC [ > 4 ; 1 m`) are not SGR escape sequences even if they end in `m`, see [`csi_sequence_len`] to skip them.
+///
+/// ```
+/// assert_eq!(ansi_gfx::sgr_sequence_len("\x1b[1;31mHello"), Some(7));
+/// assert_eq!(ansi_gfx::sgr_sequence_len("Hello"), None);
+/// assert_eq!(ansi_gfx::sgr_sequence_len("\x1b[1;3"), None);
+/// ```
+#[inline]
+pub fn sgr_sequence_len(s: &str) -> Option<usize> {
+	sgr_len(s.as_bytes())
+}
+
+pub(crate) fn sgr_len(s: &[u8]) -> Option<usize> {
+	if !s.starts_with(b"\x1b[") {
+		return None;
+	}
+	let mut params = 1;
+	for (i, &byte) in s.iter().enumerate().skip(2) {
+		match byte {
+			b'0'..=b'9' => (),
+			b';' | b':' => {
+				params += 1;
+				if params > MAX_SGR_PARAMS {
+					return None;
+				}
+			},
+			b'm' => return Some(i + 1),
+			_ => return None,
+		}
+	}
+	None
+}
+
+/// Returns the length of the CSI sequence at the start of the string.
+///
+/// A CSI sequence starts with `ESC [`, followed by an optional private prefix (`<`, `=`, `>` or `?`), parameter bytes, intermediate bytes and a final byte.
+/// This includes the SGR escape sequences and eg. the DEC private modes (`ESC [ ? 25 h`).
+/// Non-SGR sequences are skipped, not interpreted.
+/// Returns `None` if the string does not start with a complete CSI sequence or it has more than [`MAX_SGR_PARAMS`] parameters.
+///
+/// ```
+/// assert_eq!(ansi_gfx::csi_sequence_len("\x1b[?25hHello"), Some(6));
+/// assert_eq!(ansi_gfx::csi_sequence_len("\x1b[>4;1m"), Some(7));
+/// assert_eq!(ansi_gfx::csi_sequence_len("\x1b[?25"), None);
+/// ```
+pub fn csi_sequence_len(s: &str) -> Option<usize> {
+	let s = s.as_bytes();
+	if !s.starts_with(b"\x1b[") {
+		return None;
+	}
+	let mut params = 1;
+	let mut intermediate = false;
+	for (i, &byte) in s.iter().enumerate().skip(2) {
+		match byte {
+			b'<'..=b'?' if i == 2 => (),
+			b'0'..=b'9' if !intermediate => (),
+			b';' | b':' if !intermediate => {
+				params += 1;
+				if params > MAX_SGR_PARAMS {
+					return None;
+				}
+			},
+			0x20..=0x2f => intermediate = true,
+			0x40..=0x7e => return Some(i + 1),
+			_ => return None,
+		}
+	}
+	None
+}
+
+/// Counts the SGR escape sequences in the string.
+///
+/// Incomplete or invalid escape sequences are not counted.
+///
+/// ```
+/// let s = format!("{}Hello{} world{}", ansi_gfx::RED, ansi_gfx::BOLD, ansi_gfx::RESET);
+/// assert_eq!(ansi_gfx::count_sgr(&s), 3);
+/// assert_eq!(ansi_gfx::count_sgr("plain"), 0);
+/// ```
+pub fn count_sgr(s: &str) -> usize {
+	let bytes = s.as_bytes();
+	let mut count = 0;
+	let mut pos = 0;
+	while let Some(offset) = bytes[pos..].iter().position(|&byte| byte == 0x1b) {
+		pos += offset;
+		match sgr_len(&bytes[pos..]) {
+			Some(len) => {
+				count += 1;
+				pos += len;
+			},
+			None => pos += 1,
+		}
+	}
+	count
+}
+
+/// Returns the parameters following the last full reset in the SGR escape sequence, or `None` if it has no reset.
+fn after_reset(sequence: &str) -> Option<&str> {
+	let mut codes = [0u8; MAX_SGR_PARAMS];
+	let mut len = 0;
+	for param in Params::new(sequence)? {
+		*codes.get_mut(len)? = param?;
+		len += 1;
+	}
+	let mut count = 0;
+	let mut skip = None;
+	for segment in segments(&codes[..len]) {
+		count += segment.len();
+		if segment == [0] {
+			skip = Some(count);
+		}
+	}
+	let skip = skip?;
+	Some(sequence[2..sequence.len() - 1].splitn(skip + 1, ';').nth(skip).unwrap_or(""))
+}
+
+/// Re-applies a style after every full reset embedded in the content.
+///
+/// Styled content interpolated in a styled context ends the surrounding style with its resets.
+/// This adapter formats the content and emits the style again after every SGR escape sequence containing a full reset (eg. `"\x1b[0m"` or `"\x1b[m"`).
+/// Codes following the reset in the same sequence (eg. `"\x1b[0;31m"`) are emitted again after the style so they still win.
+/// Partial resets and other sequences are left alone.
+///
+/// ```
+/// let style = ansi_gfx::mode!(UNDERLINE);
+/// let content = format!("user {}text{}", ansi_gfx::RED, ansi_gfx::RESET);
+/// let s = format!("{}{}!{}", style, ansi_gfx::protect(style.erase(), &content), ansi_gfx::RESET);
+/// assert_eq!(s, "\x1b[4muser \x1b[31mtext\x1b[0m\x1b[4m!\x1b[0m");
+/// ```
+#[inline]
+pub fn protect<'a>(mode: Print<&'a [u8]>, content: &'a str) -> Protect<'a> {
+	Protect { mode, content }
+}
+
+/// Display adapter returned by [`protect`].
+#[derive(Copy, Clone, Debug)]
+#[must_use]
+pub struct Protect<'a> {
+	mode: Print<&'a [u8]>,
+	content: &'a str,
+}
+
+impl<'a> fmt::Display for Protect<'a> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let mut s = self.content;
+		while let Some(pos) = s.find('\x1b') {
+			let (text, rest) = s.split_at(pos);
+			f.write_str(text)?;
+			let len = sgr_sequence_len(rest).unwrap_or(1);
+			let (sequence, rest) = rest.split_at(len);
+			f.write_str(sequence)?;
+			if let Some(codes) = after_reset(sequence) {
+				fmt::Display::fmt(&self.mode, f)?;
+				if !codes.is_empty() {
+					write!(f, "\x1b[{}m", codes)?;
+				}
+			}
+			s = rest;
+		}
+		f.write_str(s)
+	}
+}
+
+/// Repeats a styled character, eg. to draw a horizontal rule.
+///
+/// Formats the style, the character `count` times and a [`RESET`](super::RESET) without allocating.
+/// The reset is omitted if the style is empty.
+///
+/// ```
+/// let rule = ansi_gfx::repeat(ansi_gfx::mode!(RED).erase(), '-', 3);
+/// assert_eq!(format!("{}", rule), "\x1b[31m---\x1b[0m");
+/// ```
+#[inline]
+pub fn repeat(mode: Print<&[u8]>, ch: char, count: usize) -> Repeat<'_> {
+	Repeat { mode, ch, count }
+}
+
+/// Display adapter returned by [`repeat`].
+#[derive(Copy, Clone, Debug)]
+#[must_use]
+pub struct Repeat<'a> {
+	mode: Print<&'a [u8]>,
+	ch: char,
+	count: usize,
+}
+
+impl<'a> fmt::Display for Repeat<'a> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		fmt::Display::fmt(&self.mode, f)?;
+		let mut buf = [0u8; 4];
+		let ch = self.ch.encode_utf8(&mut buf);
+		for _ in 0..self.count {
+			f.write_str(ch)?;
+		}
+		if !self.mode.__codes.is_empty() {
+			f.write_str("\x1b[0m")?;
+		}
+		Ok(())
+	}
+}
+
+/// Styles several items with a single escape sequence.
+///
+/// Formats the style once, every item in order and a single [`RESET`](super::RESET) instead of styling each item individually.
+/// The reset is omitted if the style is empty.
+///
+/// ```
+/// let s = format!("{}", ansi_gfx::apply_all(ansi_gfx::mode!(BOLD).erase(), &[1, 2, 3]));
+/// assert_eq!(s, "\x1b[1m123\x1b[0m");
+/// ```
+#[inline]
+pub fn apply_all<'a, D: fmt::Display>(mode: Print<&'a [u8]>, items: &'a [D]) -> ApplyAll<'a, D> {
+	ApplyAll { mode, items }
+}
+
+/// Display adapter returned by [`apply_all`].
+#[derive(Debug)]
+#[must_use]
+pub struct ApplyAll<'a, D> {
+	mode: Print<&'a [u8]>,
+	items: &'a [D],
+}
+
+impl<'a, D> Copy for ApplyAll<'a, D> {}
+impl<'a, D> Clone for ApplyAll<'a, D> {
+	#[inline]
+	fn clone(&self) -> Self {
+		*self
+	}
+}
+
+impl<'a, D: fmt::Display> fmt::Display for ApplyAll<'a, D> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		fmt::Display::fmt(&self.mode, f)?;
+		for item in self.items {
+			fmt::Display::fmt(item, f)?;
+		}
+		if !self.mode.__codes.is_empty() {
+			f.write_str("\x1b[0m")?;
+		}
+		Ok(())
+	}
+}
+
+/// Writes content in a style followed by a reset.
+///
+/// Writes the style, runs the body to write the content and then writes a [`RESET`](super::RESET), even if the body wrote nothing.
+/// The reset is not written if the body fails.
+///
+/// ```
+/// use std::fmt::Write;
+/// let mut s = String::new();
+/// ansi_gfx::scope(&mut s, ansi_gfx::mode!(BOLD).erase(), |s| write!(s, "{}!", 42)).unwrap();
+/// assert_eq!(s, "\x1b[1m42!\x1b[0m");
+/// ```
+pub fn scope<W: fmt::Write, F>(w: &mut W, mode: Print<&[u8]>, body: F) -> fmt::Result where F: FnOnce(&mut W) -> fmt::Result {
+	super::write_codes(mode.__codes, w)?;
+	body(w)?;
+	w.write_str("\x1b[0m")
+}
+
+/// Renders a progress bar.
+///
+/// Formats `width` space characters: the first `ratio * width` (rounded) in the filled style and the rest in the empty style, followed by a [`RESET`](super::RESET).
+/// The cells are spaces so the styles should set a background color.
+/// The empty style starts with a reset (see [`Print::fresh`]) so the filled style does not bleed into it.
+/// The ratio is clamped to `0.0..=1.0`, NaN renders an empty bar.
+///
+/// ```
+/// use ansi_gfx::mode;
+/// let bar = ansi_gfx::bar(mode!(GREEN_BG).erase(), mode!(BRIGHT_BLACK_BG).erase(), 4, 0.5);
+/// assert_eq!(format!("{}", bar), "\x1b[42m  \x1b[0;100m  \x1b[0m");
+/// ```
+#[inline]
+pub fn bar<'a>(filled: Print<&'a [u8]>, empty: Print<&'a [u8]>, width: usize, ratio: f32) -> Bar<'a> {
+	let cells = (ratio.clamp(0.0, 1.0) * width as f32 + 0.5) as usize;
+	Bar { filled, empty, width, cells: cells.min(width) }
+}
+
+/// Display adapter returned by [`bar`].
+#[derive(Copy, Clone, Debug)]
+#[must_use]
+pub struct Bar<'a> {
+	filled: Print<&'a [u8]>,
+	empty: Print<&'a [u8]>,
+	width: usize,
+	cells: usize,
+}
+
+impl<'a> fmt::Display for Bar<'a> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		if self.cells > 0 {
+			fmt::Display::fmt(&self.filled, f)?;
+			for _ in 0..self.cells {
+				f.write_str(" ")?;
+			}
+		}
+		if self.cells < self.width {
+			if self.cells > 0 {
+				fmt::Display::fmt(&self.empty.fresh(), f)?;
+			}
+			else {
+				fmt::Display::fmt(&self.empty, f)?;
+			}
+			for _ in self.cells..self.width {
+				f.write_str(" ")?;
+			}
+		}
+		f.write_str("\x1b[0m")
+	}
+}
+
+/// Styles alternating rows, eg. for zebra striped tables.
+///
+/// Formats every row in the even or odd style by its index, followed by a [`RESET`](super::RESET) and a newline.
+/// The reset is omitted if the row's style is empty.
+/// The styles should set a background color to make the stripes visible.
+///
+/// ```
+/// use ansi_gfx::mode;
+/// let zebra = ansi_gfx::zebra(&["a", "b"], mode!(BLACK_BG).erase(), mode!(BRIGHT_BLACK_BG).erase());
+/// assert_eq!(format!("{}", zebra), "\x1b[40ma\x1b[0m\n\x1b[100mb\x1b[0m\n");
+/// ```
+#[inline]
+pub fn zebra<'a>(rows: &'a [&'a str], even: Print<&'a [u8]>, odd: Print<&'a [u8]>) -> Zebra<'a> {
+	Zebra { rows, even, odd }
+}
+
+/// Display adapter returned by [`zebra`].
+#[derive(Copy, Clone, Debug)]
+#[must_use]
+pub struct Zebra<'a> {
+	rows: &'a [&'a str],
+	even: Print<&'a [u8]>,
+	odd: Print<&'a [u8]>,
+}
+
+impl<'a> fmt::Display for Zebra<'a> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		for (i, row) in self.rows.iter().enumerate() {
+			let mode = if i % 2 == 0 { &self.even } else { &self.odd };
+			fmt::Display::fmt(mode, f)?;
+			f.write_str(row)?;
+			if !mode.__codes.is_empty() {
+				f.write_str("\x1b[0m")?;
+			}
+			f.write_str("\n")?;
+		}
+		Ok(())
+	}
+}
+
+/// Renders styled text without escape sequences for terminals without ANSI support.
+///
+/// Colors are ignored, the attributes are represented with markdown-like ASCII markers around the text:
+///
+/// | Attribute | Marker |
+/// |-----------|--------|
+/// | [`BOLD`](super::BOLD) | `*text*` |
+/// | [`ITALIC`](super::ITALIC) | `/text/` |
+/// | [`UNDERLINE`](super::UNDERLINE) | `_text_` |
+/// | [`STRIKE`](super::STRIKE) | `~text~` |
+///
+/// Markers nest in the order of the table, other attributes are ignored.
+/// Attributes turned off again in the same style are not marked.
+/// Returns `None` if the buffer is too small.
+///
+/// ```
+/// let mut buf = [0u8; 32];
+/// let s = ansi_gfx::ascii_fallback(ansi_gfx::mode!(BOLD; RED; UNDERLINE).erase(), "text", &mut buf);
+/// assert_eq!(s, Some("*_text_*"));
+/// ```
+pub fn ascii_fallback<'a>(mode: Print<&[u8]>, text: &str, out: &'a mut [u8]) -> Option<&'a str> {
+	const MARKERS: [(u8, &str); 4] = [(1, "*"), (3, "/"), (4, "_"), (9, "~")];
+	let on = State::new(mode.__codes).on;
+	let mut writer = BufWriter::new(out);
+	for &(code, marker) in &MARKERS {
+		if on & 1 << code != 0 {
+			writer.write_str(marker).ok()?;
+		}
+	}
+	writer.write_str(text).ok()?;
+	for &(code, marker) in MARKERS.iter().rev() {
+		if on & 1 << code != 0 {
+			writer.write_str(marker).ok()?;
+		}
+	}
+	Some(writer.into_str())
+}
+
+/// Truncates styled text to a number of visible characters.
+///
+/// Copies at most `max` visible characters and the escape sequences in between, escape sequences are never split.
+/// Escape sequences directly following the last visible character are kept.
+/// If the text is truncated while a style is active a [`RESET`](super::RESET) is appended to avoid the style bleeding into what follows.
+///
+/// Visible characters are counted as `char`s, not terminal columns.
+/// Returns `None` if the buffer is too small.
+///
+/// ```
+/// let mut buf = [0u8; 32];
+/// let s = ansi_gfx::truncate_visible("\x1b[31mHello\x1b[0m world", 4, &mut buf);
+/// assert_eq!(s, Some("\x1b[31mHell\x1b[0m"));
+/// ```
+pub fn truncate_visible<'a>(s: &str, max: usize, out: &'a mut [u8]) -> Option<&'a str> {
+	let mut state = State::CLEARED;
+	let mut count = 0;
+	let mut pos = 0;
+	let mut cut = false;
+	while let Some(c) = s[pos..].chars().next() {
+		if let Some(len) = sgr_len(&s.as_bytes()[pos..]) {
+			state.apply_sequence(&s[pos..pos + len]);
+			pos += len;
+			continue;
+		}
+		// Other escape sequences are invisible too and never split
+		if c == '\x1b' {
+			pos += control_len(&s.as_bytes()[pos..]);
+			while !s.is_char_boundary(pos) {
+				pos += 1;
+			}
+			continue;
+		}
+		if count == max {
+			cut = true;
+			break;
+		}
+		count += 1;
+		pos += c.len_utf8();
+	}
+	let mut writer = BufWriter::new(out);
+	writer.write_str(&s[..pos]).ok()?;
+	if cut && !state.is_cleared() {
+		writer.write_str("\x1b[0m").ok()?;
+	}
+	Some(writer.into_str())
+}
+
+/// Returns the length of the control sequence starting with `ESC` at the start of the string.
+///
+/// A second `ESC` ends the sequence and starts the next one, except for the `ESC \` string terminator.
+/// Unterminated sequences extend to the end of the string.
+pub(crate) fn control_len(s: &[u8]) -> usize {
+	match s.get(1) {
+		// CSI: parameter and intermediate bytes followed by a final byte
+		Some(b'[') => match s[2..].iter().position(|&byte| byte == 0x1b || (0x40..=0x7e).contains(&byte)) {
+			Some(pos) if s[pos + 2] == 0x1b => pos + 2,
+			Some(pos) => pos + 3,
+			None => s.len(),
+		},
+		// OSC, DCS, SOS, PM and APC: a string terminated by ST or BEL
+		Some(b']' | b'P' | b'X' | b'^' | b'_') => {
+			let mut i = 2;
+			while i < s.len() {
+				match s[i] {
+					0x07 => return i + 1,
+					0x1b if s.get(i + 1) == Some(&b'\\') => return i + 2,
+					0x1b => return i,
+					_ => i += 1,
+				}
+			}
+			s.len()
+		},
+		// Any other escape sequence: intermediate bytes followed by a final byte
+		Some(_) => match s[1..].iter().position(|byte| !(0x20..=0x2f).contains(byte)) {
+			Some(pos) if s[pos + 1] == 0x1b => pos + 1,
+			Some(pos) => pos + 2,
+			None => s.len(),
+		},
+		None => 1,
+	}
+}
+
+/// Removes all control sequences from untrusted text.
+///
+/// Text from untrusted sources can contain escape sequences which change the terminal state, eg. hyperlinks (`ESC ] 8`), window titles or cursor movement.
+/// This removes every sequence introduced by `ESC`: CSI sequences (`ESC [`) up to their final byte, string sequences (`ESC ]`, `ESC P`, `ESC X`, `ESC ^` and `ESC _`) up to their `BEL` or `ESC \` terminator and any other escape sequence.
+/// The C1 control characters (U+0080 to U+009F) are removed as well since some terminals interpret them as 8-bit introducers.
+/// Unterminated sequences are removed up to the end of the text.
+/// Other control characters such as newlines and tabs are kept.
+///
+/// Returns `None` if the buffer is too small.
+///
+/// ```
+/// let mut buf = [0u8; 32];
+/// let s = ansi_gfx::sanitize("\x1b]8;;https://evil\x1b\\click\x1b]8;;\x1b\\ \x1b[31mme", &mut buf);
+/// assert_eq!(s, Some("click me"));
+/// ```
+pub fn sanitize<'a>(s: &str, out: &'a mut [u8]) -> Option<&'a str> {
+	let mut writer = BufWriter::new(out);
+	let mut rest = s;
+	while let Some(pos) = rest.find(|c: char| c == '\x1b' || ('\u{80}'..='\u{9f}').contains(&c)) {
+		writer.write_str(&rest[..pos]).ok()?;
+		let len = if rest.as_bytes()[pos] == 0x1b { control_len(&rest.as_bytes()[pos..]) } else { 2 };
+		// Never split a multibyte character of the text following a malformed sequence
+		let mut end = pos + len;
+		while !rest.is_char_boundary(end) {
+			end += 1;
+		}
+		rest = &rest[end..];
+	}
+	writer.write_str(rest).ok()?;
+	Some(writer.into_str())
+}
+
+/// Removes SGR escape sequences which do not change the style.
+///
+/// An escape sequence is dropped if the style it sets is already active, eg. the same color repeated between runs of text.
+/// SGR escape sequences which cannot be interpreted (eg. with `:` sub-parameters or double underline) are kept.
+/// The text and other escape sequences are kept intact.
+/// Returns `None` if the buffer is too small.
+///
+/// ```
+/// let mut buf = [0u8; 32];
+/// assert_eq!(ansi_gfx::coalesce("\x1b[31ma\x1b[31mb", &mut buf), Some("\x1b[31mab"));
+/// ```
+pub fn coalesce<'a>(s: &str, out: &'a mut [u8]) -> Option<&'a str> {
+	let mut writer = BufWriter::new(out);
+	// Style applied so far, unknown after an escape sequence which cannot be interpreted
+	let mut state = None;
+	let mut rest = s;
+	while let Some(pos) = rest.find('\x1b') {
+		writer.write_str(&rest[..pos]).ok()?;
+		rest = &rest[pos..];
+		let Some(len) = sgr_sequence_len(rest) else {
+			writer.write_str("\x1b").ok()?;
+			rest = &rest[1..];
+			continue;
+		};
+		let sequence = &rest[..len];
+		rest = &rest[len..];
+		let valid = Params::new(sequence).is_some_and(|mut params| params.all(|param| param.is_some()));
+		// Codes without a modelled reset (eg. double underline) cannot be tracked
+		let mut touched = State::default();
+		touched.apply_sequence(sequence);
+		if !valid || touched.other != [0; 4] {
+			state = None;
+			writer.write_str(sequence).ok()?;
+			continue;
+		}
+		let mut next: State = state.unwrap_or_default();
+		next.apply_sequence(sequence);
+		if state != Some(next) {
+			writer.write_str(sequence).ok()?;
+			state = Some(next);
+		}
+	}
+	writer.write_str(rest).ok()?;
+	Some(writer.into_str())
+}
+
+/// Iterates over the visible characters and their byte offsets.
+///
+/// SGR escape sequences are skipped, the offsets point into the original string.
+///
+/// ```
+/// let mut iter = ansi_gfx::visible_char_indices("\x1b[1mhi\x1b[0m!");
+/// assert_eq!(iter.next(), Some((4, 'h')));
+/// assert_eq!(iter.next(), Some((5, 'i')));
+/// assert_eq!(iter.next(), Some((10, '!')));
+/// assert_eq!(iter.next(), None);
+/// ```
+#[inline]
+pub fn visible_char_indices(s: &str) -> VisibleCharIndices<'_> {
+	VisibleCharIndices { s, pos: 0 }
+}
+
+/// Iterator returned by [`visible_char_indices`].
+#[derive(Clone, Debug)]
+pub struct VisibleCharIndices<'a> {
+	s: &'a str,
+	pos: usize,
+}
+
+impl<'a> Iterator for VisibleCharIndices<'a> {
+	type Item = (usize, char);
+	fn next(&mut self) -> Option<(usize, char)> {
+		while let Some(len) = sgr_len(&self.s.as_bytes()[self.pos..]) {
+			self.pos += len;
+		}
+		let c = self.s[self.pos..].chars().next()?;
+		let pos = self.pos;
+		self.pos += c.len_utf8();
+		Some((pos, c))
+	}
+}
+
+/// Iterates over the visible characters in reverse order.
+///
+/// SGR escape sequences are skipped, the characters are the same as [`visible_char_indices`] yields but starting from the end.
+///
+/// ```
+/// let s: String = ansi_gfx::visible_rchars("\x1b[1mab\x1b[31mc\x1b[0m").collect();
+/// assert_eq!(s, "cba");
+/// ```
+#[inline]
+pub fn visible_rchars(s: &str) -> VisibleRChars<'_> {
+	VisibleRChars { s }
+}
+
+/// Iterator returned by [`visible_rchars`].
+#[derive(Clone, Debug)]
+pub struct VisibleRChars<'a> {
+	s: &'a str,
+}
+
+impl<'a> VisibleRChars<'a> {
+	/// Returns the length of the SGR escape sequence at the end of the string.
+	fn sgr_len_back(s: &[u8]) -> Option<usize> {
+		let (&b'm', rest) = s.split_last()? else { return None };
+		let params = rest.iter().rev().take_while(|&&byte| matches!(byte, b'0'..=b'9' | b';' | b':')).count();
+		let rest = &rest[..rest.len() - params];
+		if !rest.ends_with(b"\x1b[") {
+			return None;
+		}
+		let len = params + 3;
+		if sgr_len(&s[s.len() - len..]) == Some(len) { Some(len) } else { None }
+	}
+}
+
+impl<'a> Iterator for VisibleRChars<'a> {
+	type Item = char;
+	fn next(&mut self) -> Option<char> {
+		while let Some(len) = Self::sgr_len_back(self.s.as_bytes()) {
+			self.s = &self.s[..self.s.len() - len];
+		}
+		let c = self.s.chars().next_back()?;
+		self.s = &self.s[..self.s.len() - c.len_utf8()];
+		Some(c)
+	}
+}
+
+/// Writer which strips SGR escape sequences.
+///
+/// Everything written through it is forwarded to the inner writer without its SGR escape sequences,
+/// for reusing the same formatting code for styled and plain output.
+/// CSI sequences with a private parameter prefix (`<`, `=`, `>` or `?`) such as the [DEC private modes](super::private_modes) are stripped as well, they are skipped, not interpreted.
+/// Escape sequences split across writes are handled, a pending incomplete sequence is held back until it completes.
+/// Escape sequences longer than 64 bytes are forwarded as is.
+///
+/// ```
+/// use std::fmt::Write;
+/// let mut writer = ansi_gfx::StripWriter::new(String::new());
+/// write!(writer, "{}error{}: failed", ansi_gfx::mode!(BOLD; RED), ansi_gfx::RESET).unwrap();
+/// assert_eq!(writer.into_inner().unwrap(), "error: failed");
+/// ```
+pub struct StripWriter<W: fmt::Write> {
+	inner: W,
+	pending: [u8; 64],
+	len: usize,
+}
+
+impl<W: fmt::Write> StripWriter<W> {
+	/// Wraps a writer.
+	#[inline]
+	pub fn new(inner: W) -> StripWriter<W> {
+		StripWriter { inner, pending: [0; 64], len: 0 }
+	}
+
+	/// Returns a reference to the inner writer.
+	#[inline]
+	pub fn get_ref(&self) -> &W {
+		&self.inner
+	}
+
+	/// Forwards a pending incomplete escape sequence as is and returns the inner writer.
+	pub fn into_inner(mut self) -> Result<W, fmt::Error> {
+		self.flush_pending()?;
+		Ok(self.inner)
+	}
+
+	fn flush_pending(&mut self) -> fmt::Result {
+		// The pending bytes are always ASCII
+		let len = self.len;
+		self.len = 0;
+		self.inner.write_str(unsafe { core::str::from_utf8_unchecked(&self.pending[..len]) })
+	}
+}
+
+impl<W: fmt::Write> fmt::Write for StripWriter<W> {
+	fn write_str(&mut self, s: &str) -> fmt::Result {
+		let bytes = s.as_bytes();
+		let mut i = 0;
+		while i < bytes.len() {
+			if self.len == 0 {
+				// Forward the text up to the next escape sequence
+				let end = bytes[i..].iter().position(|&byte| byte == 0x1b).map_or(bytes.len(), |pos| i + pos);
+				self.inner.write_str(&s[i..end])?;
+				if end < bytes.len() {
+					self.pending[0] = 0x1b;
+					self.len = 1;
+				}
+				i = end + 1;
+				continue;
+			}
+			let byte = bytes[i];
+			let private = self.len > 2 && matches!(self.pending[2], b'<'..=b'?');
+			let continues = match self.len {
+				1 => byte == b'[',
+				2 => matches!(byte, b'0'..=b'9' | b';' | b':' | b'<'..=b'?'),
+				_ if private => matches!(byte, b'0'..=b'9' | b';' | b':' | 0x20..=0x2f),
+				_ => matches!(byte, b'0'..=b'9' | b';' | b':'),
+			};
+			let last = if private { (0x40..=0x7e).contains(&byte) } else { byte == b'm' };
+			if last && self.len >= 2 {
+				// Complete SGR escape sequence or private CSI sequence, drop it
+				self.len = 0;
+				i += 1;
+			}
+			else if continues && self.len < self.pending.len() {
+				self.pending[self.len] = byte;
+				self.len += 1;
+				i += 1;
+			}
+			else {
+				// Not an SGR escape sequence after all, the byte is processed again
+				self.flush_pending()?;
+			}
+		}
+		Ok(())
+	}
+}
+
+/// Writer which resets the style before every newline.
+///
+/// Everything written through it is forwarded to the inner writer, a [`RESET`](super::RESET) is inserted before a `\n` while a style is active.
+/// This prevents styles from bleeding into the next line in terminals and pagers which keep the style across lines.
+/// The style is not restored after the newline.
+/// SGR escape sequences split across writes are tracked, escape sequences longer than 64 bytes are ignored.
+///
+/// ```
+/// use std::fmt::Write;
+/// let mut writer = ansi_gfx::LineResetWriter::new(String::new());
+/// write!(writer, "{}error\nplain\n", ansi_gfx::RED).unwrap();
+/// assert_eq!(writer.into_inner(), "\x1b[31merror\x1b[0m\nplain\n");
+/// ```
+pub struct LineResetWriter<W: fmt::Write> {
+	inner: W,
+	state: State,
+	pending: [u8; 64],
+	len: usize,
+}
+
+impl<W: fmt::Write> LineResetWriter<W> {
+	/// Wraps a writer, the terminal is assumed to have the default style.
+	#[inline]
+	pub fn new(inner: W) -> LineResetWriter<W> {
+		LineResetWriter { inner, state: State::CLEARED, pending: [0; 64], len: 0 }
+	}
+
+	/// Returns a reference to the inner writer.
+	#[inline]
+	pub fn get_ref(&self) -> &W {
+		&self.inner
+	}
+
+	/// Returns the inner writer.
+	#[inline]
+	pub fn into_inner(self) -> W {
+		self.inner
+	}
+
+	/// Tracks the byte of a pending escape sequence, returns false if the byte does not continue it.
+	fn track(&mut self, byte: u8) -> bool {
+		let continues = match self.len {
+			1 => byte == b'[',
+			_ => matches!(byte, b'0'..=b'9' | b';' | b':' | b'm'),
+		};
+		if !continues || self.len == self.pending.len() {
+			self.len = 0;
+			return false;
+		}
+		self.pending[self.len] = byte;
+		self.len += 1;
+		if byte == b'm' {
+			// The pending bytes are always ASCII
+			let sequence = unsafe { core::str::from_utf8_unchecked(&self.pending[..self.len]) };
+			self.state.apply_sequence(sequence);
+			self.len = 0;
+		}
+		true
+	}
+}
+
+impl<W: fmt::Write> fmt::Write for LineResetWriter<W> {
+	fn write_str(&mut self, s: &str) -> fmt::Result {
+		let mut start = 0;
+		for (i, &byte) in s.as_bytes().iter().enumerate() {
+			if self.len > 0 && self.track(byte) {
+				continue;
+			}
+			if byte == 0x1b {
+				self.pending[0] = byte;
+				self.len = 1;
+			}
+			else if byte == b'\n' && !self.state.is_cleared() {
+				self.inner.write_str(&s[start..i])?;
+				self.inner.write_str("\x1b[0m")?;
+				self.state = State::CLEARED;
+				start = i;
+			}
+		}
+		self.inner.write_str(&s[start..])
+	}
+}
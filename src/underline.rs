@@ -0,0 +1,32 @@
+use core::fmt;
+
+/// Styled underline.
+///
+/// Formats as the `4:n` sub-parameter form which cannot be expressed as a [`Code`](super::Code) and thus cannot be combined in `mode!`.
+/// Turn it off with [`RESET_UNDERLINE`](super::RESET_UNDERLINE).
+///
+/// Support is limited to modern terminals such as Kitty, WezTerm and VTE based terminals.
+/// Other terminals ignore the sequence or mistake the sub-parameter for a separate code (eg. `4:3` for underline and italic).
+///
+/// ```
+/// assert_eq!(format!("{}", ansi_gfx::CURLY_UNDERLINE), "\x1b[4:3m");
+/// ```
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+#[must_use]
+pub struct Underline {
+	style: u8,
+}
+
+/// Curly underline (`4:3`).
+pub const CURLY_UNDERLINE: Underline = Underline { style: 3 };
+/// Dotted underline (`4:4`).
+pub const DOTTED_UNDERLINE: Underline = Underline { style: 4 };
+/// Dashed underline (`4:5`).
+pub const DASHED_UNDERLINE: Underline = Underline { style: 5 };
+
+impl fmt::Display for Underline {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let buf = [0x1b, b'[', b'4', b':', b'0' + self.style, b'm'];
+		f.write_str(unsafe { core::str::from_utf8_unchecked(&buf) })
+	}
+}